@@ -0,0 +1,133 @@
+//! Canonical encodings of the payloads of secured entries
+//!
+//! Signatures are computed over the canonical JSON encoding: compact JSON with the keys of
+//! every object sorted by their UTF-8 bytes. CIDs are computed over the canonical DAG-CBOR
+//! encoding, where keys are sorted by length first and then by their bytes.
+//!
+//! Integral floats like 1.0 are encoded as integers in both encodings. In signed payloads,
+//! numbers are restricted to integers in the range that JavaScript represents exactly,
+//! -(2^53 - 1) to 2^53 - 1, and any other number is rejected, so that no platform has to
+//! agree on how to format floats. Data entries can contain any number: in their CIDs, other
+//! numbers are encoded as the shortest CBOR float that represents them exactly.
+//!
+//! Both encodings only depend on the JSON value of the payload, so any platform that
+//! reproduces them can verify the signatures and addresses of our entries byte-for-byte.
+
+use hdk::prelude::*;
+use serde_cbor;
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+/**
+ * Returns the canonical JSON string for the given payload
+ */
+pub fn to_canonical_json(payload: JsonString) -> ZomeApiResult<String> {
+    let value = normalize_numbers(parse(payload)?, true)?;
+    let value = sort_keys(value, &json_key_order);
+
+    serde_json::to_string(&value)
+        .map_err(|e| ZomeApiError::from(format!("Could not serialize canonical json: {:?}", e)))
+}
+
+/**
+ * Returns the canonical DAG-CBOR bytes for the given payload
+ */
+pub fn to_canonical_cbor(payload: JsonString) -> ZomeApiResult<Vec<u8>> {
+    let value = normalize_numbers(parse(payload)?, false)?;
+    let value = sort_keys(value, &cbor_key_order);
+
+    serde_cbor::to_vec(&value)
+        .map_err(|e| ZomeApiError::from(format!("Could not serialize canonical cbor: {:?}", e)))
+}
+
+/** Private helpers **/
+
+// Largest integer that JavaScript represents exactly, 2^53 - 1
+const MAX_SAFE_INTEGER: i64 = 9_007_199_254_740_991;
+
+fn parse(payload: JsonString) -> ZomeApiResult<Value> {
+    serde_json::from_str(payload.to_string().as_str())
+        .map_err(|e| ZomeApiError::from(format!("Error deserializing json: {:?}", e)))
+}
+
+/**
+ * Converts every safe integer to an integer, and either fails on any other number
+ * if strict or leaves it as it is
+ */
+fn normalize_numbers(value: Value, strict: bool) -> ZomeApiResult<Value> {
+    match value {
+        Value::Number(number) => match safe_integer(&number) {
+            Some(integer) => Ok(Value::Number(Number::from(integer))),
+            None if !strict => Ok(Value::Number(number)),
+            None => Err(ZomeApiError::from(format!(
+                "Signed payloads only support integers between -(2^53 - 1) and 2^53 - 1, found {}",
+                number
+            ))),
+        },
+        Value::Object(map) => {
+            let mut normalized = Map::new();
+            for (key, value) in map.into_iter() {
+                normalized.insert(key, normalize_numbers(value, strict)?);
+            }
+            Ok(Value::Object(normalized))
+        }
+        Value::Array(values) => Ok(Value::Array(
+            values
+                .into_iter()
+                .map(|value| normalize_numbers(value, strict))
+                .collect::<ZomeApiResult<Vec<Value>>>()?,
+        )),
+        other => Ok(other),
+    }
+}
+
+fn safe_integer(number: &Number) -> Option<i64> {
+    let integer = match (number.as_i64(), number.as_f64()) {
+        (Some(integer), _) => integer,
+        (None, Some(float)) if float.fract() == 0.0 && float.abs() <= MAX_SAFE_INTEGER as f64 => {
+            float as i64
+        }
+        _ => return None,
+    };
+
+    match integer.abs() <= MAX_SAFE_INTEGER {
+        true => Some(integer),
+        false => None,
+    }
+}
+
+fn json_key_order(k1: &String, k2: &String) -> Ordering {
+    k1.as_bytes().cmp(k2.as_bytes())
+}
+
+fn cbor_key_order(k1: &String, k2: &String) -> Ordering {
+    k1.len()
+        .cmp(&k2.len())
+        .then_with(|| k1.as_bytes().cmp(k2.as_bytes()))
+}
+
+/**
+ * Rebuilds the given value inserting the keys of every object in the given order,
+ * which serde_json preserves when serializing
+ */
+fn sort_keys(value: Value, order: &dyn Fn(&String, &String) -> Ordering) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(k1, _), (k2, _)| order(k1, k2));
+
+            let mut sorted = Map::new();
+            for (key, value) in entries.into_iter() {
+                sorted.insert(key, sort_keys(value, order));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| sort_keys(value, order))
+                .collect(),
+        ),
+        other => other,
+    }
+}
@@ -1,15 +1,18 @@
 use crate::proof::{Proof, Secured};
-use crate::utils::create_entry;
+use crate::proxy;
+use crate::utils::{self, create_entry};
 use hdk::{
     entry_definition::ValidatingEntryType,
-    error::ZomeApiResult,
+    error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{
-        dna::entry_types::Sharing, entry::Entry, validation::EntryValidationData,
+        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
     },
     holochain_json_api::{error::JsonError, json::JsonString},
-    holochain_persistence_api::cas::content::Address,
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
     AGENT_ADDRESS,
 };
+use std::collections::HashSet;
+use std::convert::TryFrom;
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct CommitData {
@@ -25,7 +28,32 @@ pub struct CommitData {
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct Commit {
     payload: CommitData,
+    // Proof of the first creator
     proof: Proof,
+    // Proofs of the rest of the creators, in the same order as creatorsIds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    coauthorsProofs: Vec<Proof>,
+}
+
+/**
+ * Commit with multiple creators which is still gathering their signatures
+ */
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CommitDraft {
+    payload: CommitData,
+    // One slot for each creator, in the same order as creatorsIds
+    proofs: Vec<Option<Proof>>,
+    // Draft created by draft_commit, of which this one is a newer version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    originalId: Option<Address>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CommitDraftStatus {
+    // Address of the original draft, which identifies it across all its versions
+    pub draft_address: Address,
+    pub missing_creators: Vec<Address>,
+    pub commit_address: Option<Address>,
 }
 
 impl Commit {
@@ -45,6 +73,101 @@ impl Commit {
 
         Commit::from_data(commit_data)
     }
+
+    fn from_proofs(commit_data: CommitData, mut proofs: Vec<Proof>) -> Commit {
+        let coauthors_proofs = proofs.split_off(1);
+
+        Commit {
+            payload: commit_data,
+            proof: proofs.remove(0),
+            coauthorsProofs: coauthors_proofs,
+        }
+    }
+
+    pub fn commit_data(&self) -> &CommitData {
+        &self.payload
+    }
+}
+
+impl CommitDraft {
+    pub fn entry(&self) -> Entry {
+        Entry::App("commit_draft".into(), self.into())
+    }
+
+    pub fn missing_creators(&self) -> Vec<Address> {
+        self.payload
+            .creatorsIds
+            .iter()
+            .zip(self.proofs.iter())
+            .filter(|(_, proof)| proof.is_none())
+            .map(|(creator_id, _)| creator_id.clone())
+            .collect()
+    }
+
+    /**
+     * Adds the signature of the calling agent to its slot in the draft
+     */
+    pub fn sign(&mut self) -> ZomeApiResult<()> {
+        let position = self
+            .payload
+            .creatorsIds
+            .iter()
+            .position(|creator_id| creator_id.clone() == AGENT_ADDRESS.clone())
+            .ok_or(ZomeApiError::from(String::from(
+                "Only the creators of the commit can sign it",
+            )))?;
+
+        self.proofs[position] = Some(Proof::from(self.payload.clone().into())?);
+
+        Ok(())
+    }
+
+    /**
+     * Fills the empty slots of this draft with the proofs gathered in the given version of it
+     */
+    pub fn merge_proofs(&mut self, version: CommitDraft) {
+        for (slot, proof) in self.proofs.iter_mut().zip(version.proofs.into_iter()) {
+            if slot.is_none() {
+                *slot = proof;
+            }
+        }
+    }
+
+    /**
+     * Checks that the draft has at least one creator and that none of them is repeated
+     */
+    pub fn check_creators(&self) -> Result<(), String> {
+        let creators_ids = &self.payload.creatorsIds;
+
+        if creators_ids.is_empty() {
+            return Err(String::from("A commit must have at least one creator"));
+        }
+
+        let unique_creators: HashSet<&Address> = creators_ids.iter().collect();
+        match unique_creators.len() == creators_ids.len() {
+            true => Ok(()),
+            false => Err(String::from("The creators of a commit cannot be repeated")),
+        }
+    }
+
+    /**
+     * Verifies the signatures already gathered in the draft
+     */
+    pub fn verify(&self) -> Result<(), String> {
+        self.check_creators()?;
+
+        if self.payload.creatorsIds.len() != self.proofs.len() {
+            return Err(String::from("The draft must have a slot for every creator"));
+        }
+
+        for (creator_id, proof) in self.payload.creatorsIds.iter().zip(self.proofs.iter()) {
+            if let Some(p) = proof {
+                p.verify_for(creator_id, self.payload.clone().into())?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Secured<CommitData> for Commit {
@@ -54,6 +177,7 @@ impl Secured<CommitData> for Commit {
         Ok(Commit {
             payload: commit_data,
             proof: proof,
+            coauthorsProofs: vec![],
         })
     }
 
@@ -61,16 +185,18 @@ impl Secured<CommitData> for Commit {
         Entry::App("commit".into(), self.into())
     }
 
-    fn creator_id(&self) -> Address {
-        self.payload.creatorsIds[0].to_owned()
+    fn creators_ids(&self) -> Vec<Address> {
+        self.payload.creatorsIds.to_owned()
     }
 
     fn payload(&self) -> JsonString {
         self.payload.to_owned().into()
     }
 
-    fn proof(&self) -> Proof {
-        self.proof.to_owned()
+    fn proofs(&self) -> Vec<Proof> {
+        let mut proofs = vec![self.proof.to_owned()];
+        proofs.extend(self.coauthorsProofs.to_owned());
+        proofs
     }
 }
 
@@ -80,12 +206,21 @@ pub fn definition() -> ValidatingEntryType {
         description: "a commit entry",
         sharing: Sharing::Public,
         validation_package: || {
-            hdk::ValidationPackageDefinition::Entry
+            // Hard links to entries of the author that have not reached the DHT yet
+            // are resolved against its source chain. This ships the whole chain of the
+            // author with every commit, so validation cost grows with the chain length
+            hdk::ValidationPackageDefinition::ChainEntries
         },
         validation: |validation_data: hdk::EntryValidationData<Commit>| {
             match validation_data {
-                EntryValidationData::Create { .. } => {
-                    Ok(())
+                EntryValidationData::Create { entry, validation_data } => {
+                    let chain_entries = validation_data
+                        .package
+                        .source_chain_entries
+                        .unwrap_or_default();
+
+                    validate_hard_links(&entry, &chain_entries)?;
+                    Proof::verify(entry)
                 },
                 _ => Err("Cannot modify or delete commits".into())
             }
@@ -93,6 +228,67 @@ pub fn definition() -> ValidatingEntryType {
     )
 }
 
+pub fn draft_definition() -> ValidatingEntryType {
+    entry!(
+        name: "commit_draft",
+        description: "a commit gathering the signatures of its creators",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: |validation_data: hdk::EntryValidationData<CommitDraft>| {
+            match validation_data {
+                EntryValidationData::Create { entry, .. } => {
+                    entry.verify()
+                },
+                _ => Err("Cannot modify or delete commit drafts".into())
+            }
+        },
+        links: [
+            from!(
+                "%agent_id",
+                link_type: "agent->commit_draft",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, _) = utils::split_link_validation_data(validation_data);
+                    let draft: CommitDraft =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    match draft.payload.creatorsIds.contains(link_data.link().base()) {
+                        true => Ok(()),
+                        false => Err("Drafts can only be linked from their creators".into()),
+                    }
+                }
+            ),
+            to!(
+                "commit_draft",
+                link_type: "draft_version",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, _) = utils::split_link_validation_data(validation_data);
+                    let original: CommitDraft =
+                        hdk::utils::get_as_type(link_data.link().base().clone())?;
+                    let version: CommitDraft =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    let is_version = version.originalId == Some(link_data.link().base().clone());
+                    let same_payload =
+                        JsonString::from(original.payload) == JsonString::from(version.payload);
+
+                    match is_version && same_payload {
+                        true => Ok(()),
+                        false => Err("Only versions of a draft can be linked from it".into()),
+                    }
+                }
+            )
+        ]
+    )
+}
+
 // Public handlers
 
 /**
@@ -108,3 +304,234 @@ pub fn create_commit(
 
     create_entry(commit)
 }
+
+/**
+ * Returns the commit stored at the given address or proxy, along with its internal address
+ */
+pub fn get_commit(commit_address: &Address) -> ZomeApiResult<Option<(Address, Commit)>> {
+    let internal_address = match proxy::internal_address(commit_address)? {
+        Some(address) => address,
+        None => return Ok(None),
+    };
+
+    match hdk::get_entry(&internal_address)? {
+        Some(Entry::App(entry_type, value)) => match entry_type.to_string().as_str() {
+            "commit" => match Commit::try_from(value) {
+                Ok(commit) => Ok(Some((internal_address, commit))),
+                Err(_) => Err(ZomeApiError::from(String::from(
+                    "Could not deserialize commit",
+                ))),
+            },
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/**
+ * Create a draft of a commit with multiple creators, signed by the calling agent
+ * and linked from every creator that is an agent of this app
+ */
+pub fn draft_commit(
+    dataId: Address,
+    parentsIds: Vec<Address>,
+    message: String,
+    timestamp: u128,
+    creatorsIds: Vec<Address>,
+) -> ZomeApiResult<CommitDraftStatus> {
+    let commit_data = CommitData {
+        dataId,
+        parentsIds,
+        timestamp,
+        message,
+        creatorsIds: creatorsIds.clone(),
+    };
+
+    let mut draft = CommitDraft {
+        payload: commit_data,
+        proofs: creatorsIds.iter().map(|_| None).collect(),
+        originalId: None,
+    };
+    draft.check_creators().map_err(ZomeApiError::from)?;
+    draft.sign()?;
+
+    save_draft(draft)
+}
+
+/**
+ * Add the signature of the calling agent to the latest version of the given draft,
+ * creating the commit once all its creators have signed it
+ *
+ * Every signature is saved in a new version of the draft, linked from the original one,
+ * and the signatures of all versions are merged so that concurrent signers don't drop each other's
+ */
+pub fn sign_commit(draft_address: Address) -> ZomeApiResult<CommitDraftStatus> {
+    let mut draft = get_merged_draft(&draft_address)?;
+    draft.sign()?;
+
+    save_draft(draft)
+}
+
+/**
+ * Returns the status of the given draft, with the signatures gathered in all its versions
+ */
+pub fn get_latest_draft(draft_address: Address) -> ZomeApiResult<CommitDraftStatus> {
+    let draft = get_merged_draft(&draft_address)?;
+    let missing_creators = draft.missing_creators();
+
+    let commit_address = match missing_creators.is_empty() {
+        true => {
+            let commit_address = hdk::entry_address(&draft_to_commit(draft.clone()).entry())?;
+            hdk::get_entry(&commit_address)?.map(|_| commit_address)
+        }
+        false => None,
+    };
+
+    Ok(CommitDraftStatus {
+        draft_address: draft.originalId.unwrap_or(draft_address),
+        missing_creators,
+        commit_address,
+    })
+}
+
+/**
+ * Returns the original drafts of the commits which the given agent is a creator of
+ */
+pub fn get_agent_drafts(agent_address: &Address) -> ZomeApiResult<Vec<Address>> {
+    let links = hdk::get_links(
+        agent_address,
+        LinkMatch::Exactly("agent->commit_draft"),
+        LinkMatch::Any,
+    )?;
+
+    Ok(links.addresses())
+}
+
+/** Private helpers **/
+
+/**
+ * Checks that the parents of the commit resolve to commits and its data to a data entry,
+ * unless dangling references are allowed and they cannot be resolved in this app
+ */
+fn validate_hard_links(commit: &Commit, chain_entries: &Vec<Entry>) -> Result<(), String> {
+    let allow_dangling = utils::allow_dangling_references();
+
+    for parent_id in commit.payload.parentsIds.iter() {
+        match resolve_hard_link(parent_id, chain_entries)? {
+            Some(ref entry_type) if entry_type == "commit" => (),
+            Some(_) => return Err(format!("Parent {} is not a commit", parent_id)),
+            None if allow_dangling => (),
+            None => return Err(format!("Could not resolve parent {}", parent_id)),
+        }
+    }
+
+    match resolve_hard_link(&commit.payload.dataId, chain_entries)? {
+        Some(ref entry_type) if entry_type == "data" => Ok(()),
+        Some(_) => Err(format!("{} is not a data entry", commit.payload.dataId)),
+        None if allow_dangling => Ok(()),
+        None => Err(format!("Could not resolve data {}", commit.payload.dataId)),
+    }
+}
+
+/**
+ * Returns the type of the entry the given address or CID points to, looking first in the
+ * source chain of the author of the commit and then in the DHT
+ */
+fn resolve_hard_link(
+    address: &Address,
+    chain_entries: &Vec<Entry>,
+) -> ZomeApiResult<Option<String>> {
+    let app_entries = chain_entries.iter().filter_map(|entry| match entry {
+        Entry::App(entry_type, content) => Some((entry, entry_type.to_string(), content)),
+        _ => None,
+    });
+
+    let mut hard_link_entries = Vec::new();
+    for (entry, entry_type, content) in app_entries {
+        if entry.address() == *address {
+            return Ok(Some(entry_type));
+        }
+        if entry_type == "commit" || entry_type == "data" {
+            hard_link_entries.push((entry_type, content));
+        }
+    }
+
+    // Only compute CIDs once no address matches, and only for entries hard links point to
+    for (entry_type, content) in hard_link_entries {
+        if proxy::get_raw_multihash(content.clone()).ok().as_ref() == Some(address) {
+            return Ok(Some(entry_type));
+        }
+    }
+
+    utils::resolve_entry_type(address)
+}
+
+/**
+ * Returns the original version of the given draft with the proofs of all its versions,
+ * ready to be saved as a new version of it
+ */
+fn get_merged_draft(draft_address: &Address) -> ZomeApiResult<CommitDraft> {
+    let draft: CommitDraft = hdk::utils::get_as_type(draft_address.clone())?;
+
+    let (original_address, mut merged) = match draft.originalId.clone() {
+        Some(original_address) => {
+            let original: CommitDraft = hdk::utils::get_as_type(original_address.clone())?;
+            (original_address, original)
+        }
+        None => (draft_address.clone(), draft),
+    };
+
+    let versions = hdk::get_links(
+        &original_address,
+        LinkMatch::Exactly("draft_version"),
+        LinkMatch::Any,
+    )?;
+    for version_address in versions.addresses() {
+        let version: CommitDraft = hdk::utils::get_as_type(version_address)?;
+        merged.merge_proofs(version);
+    }
+
+    merged.originalId = Some(original_address);
+    Ok(merged)
+}
+
+fn draft_to_commit(draft: CommitDraft) -> Commit {
+    let proofs = draft.proofs.into_iter().filter_map(|proof| proof).collect();
+    Commit::from_proofs(draft.payload, proofs)
+}
+
+/**
+ * Saves the draft, linking new versions from the original one and original drafts
+ * from their creators, and creates the commit if all the creators have signed it
+ */
+fn save_draft(draft: CommitDraft) -> ZomeApiResult<CommitDraftStatus> {
+    let version_address = hdk::commit_entry(&draft.entry())?;
+
+    let draft_address = match draft.originalId.clone() {
+        Some(original_address) => {
+            hdk::link_entries(&original_address, &version_address, "draft_version", "")?;
+            original_address
+        }
+        None => {
+            for creator_id in draft.payload.creatorsIds.iter() {
+                // Creators from other platforms have no agent entry to link from
+                if let Some(Entry::AgentId(_)) = hdk::get_entry(creator_id)? {
+                    hdk::link_entries(creator_id, &version_address, "agent->commit_draft", "")?;
+                }
+            }
+            version_address
+        }
+    };
+
+    let missing_creators = draft.missing_creators();
+    let commit_address = match missing_creators.is_empty() {
+        true => Some(create_entry(draft_to_commit(draft))?),
+        false => None,
+    };
+
+    Ok(CommitDraftStatus {
+        draft_address,
+        missing_creators,
+        commit_address,
+    })
+}
@@ -1,4 +1,4 @@
-use crate::versioned_tags;
+use crate::{utils, versioned_tags};
 use hdk::prelude::*;
 
 // Public handlers
@@ -10,11 +10,18 @@ pub fn context_address(context: &String) -> ZomeApiResult<Address> {
     holochain_anchors::anchor("context".into(), context.clone())
 }
 
+/**
+ * Returns the address of the given context without creating it
+ */
+pub fn context_anchor_address(context: &String) -> ZomeApiResult<Address> {
+    utils::anchor_address("context".into(), context.clone())
+}
+
 /**
  * Return all perspectives associated to the given context
  */
 pub fn get_context_perspectives(context: String) -> ZomeApiResult<Vec<Address>> {
-    let address = context_address(&context)?;
+    let address = context_anchor_address(&context)?;
 
     let links = hdk::get_links(
         &address,
@@ -35,7 +42,7 @@ pub fn update_perspective_context(
     // Remove previous link from context if existent
     let previous_context = get_perspective_context(&perspective_address)?;
     if let Some(c) = previous_context {
-        let previous_context_address = context_address(&c)?;
+        let previous_context_address = context_anchor_address(&c)?;
         hdk::remove_link(
             &previous_context_address,
             &perspective_address,
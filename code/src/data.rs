@@ -1,5 +1,6 @@
-use crate::proxy;
+use crate::{commit, proxy};
 use hdk::prelude::*;
+use serde_json::Value;
 
 pub fn definition() -> ValidatingEntryType {
     entry!(
@@ -34,3 +35,48 @@ pub fn create_data(data: JsonString, proxy_address: Option<Address>) -> ZomeApiR
 
     proxy::set_entry_proxy(&entry, &proxy_address)
 }
+
+/**
+ * Returns the contents of the data entry stored at the given address or proxy
+ */
+pub fn get_data(data_address: &Address) -> ZomeApiResult<JsonString> {
+    let internal_address = match proxy::internal_address(data_address)? {
+        Some(address) => Ok(address),
+        None => Err(ZomeApiError::from(format!("Could not find data {}", data_address))),
+    }?;
+
+    match hdk::get_entry(&internal_address)? {
+        Some(Entry::App(entry_type, value)) if entry_type.to_string() == "data" => Ok(value),
+        _ => Err(ZomeApiError::from(format!(
+            "Entry {} is not a data entry",
+            data_address
+        ))),
+    }
+}
+
+/**
+ * Returns the contents of the given data entry as a JSON value
+ */
+pub fn get_data_value(data_address: &Address) -> ZomeApiResult<Value> {
+    let data = get_data(data_address)?;
+
+    serde_json::from_str(data.to_string().as_str())
+        .map_err(|e| ZomeApiError::from(format!("Error deserializing data: {:?}", e)))
+}
+
+/**
+ * Returns the data of the given commit as a JSON value
+ */
+pub fn get_commit_data(commit_address: &Address) -> ZomeApiResult<Value> {
+    match commit::get_commit(commit_address)? {
+        Some((_, commit)) => get_data_value(&commit.commit_data().dataId),
+        None => Err(ZomeApiError::from(format!(
+            "Could not find commit {}",
+            commit_address
+        ))),
+    }
+}
+
+pub fn to_json_string(value: &Value) -> JsonString {
+    JsonString::from_json(&value.to_string())
+}
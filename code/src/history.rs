@@ -0,0 +1,221 @@
+use crate::commit::{self, Commit};
+use hdk::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct HistoryCommit {
+    // Address of the commit as referenced by its descendants
+    pub id: Address,
+    // Address of the commit entry in this app
+    pub address: Address,
+    pub commit: Commit,
+}
+
+/**
+ * State of a paused history walk, from which the next page resumes
+ *
+ * The cursor keeps every commit returned so far, since any of them can still be reached
+ * through a later merge, so it grows linearly with the part of the history already walked
+ */
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct HistoryCursor {
+    // Commits still to be visited, in order
+    pub queue: Vec<Address>,
+    // Internal addresses of the commits already returned
+    pub visited: Vec<Address>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct CommitHistory {
+    pub commits: Vec<HistoryCommit>,
+    pub next_cursor: Option<HistoryCursor>,
+}
+
+/**
+ * Breadth-first walk over the ancestors of the given commits, starting with the commits themselves
+ *
+ * Each commit is visited once, even if it's referenced through different proxies,
+ * and parents are visited in the order they appear in parentsIds, so the order is stable
+ * for a given commit. Parents that cannot be resolved in this app are skipped.
+ */
+pub struct HistoryWalk {
+    queue: VecDeque<Address>,
+    visited: HashSet<Address>,
+    // Parents of the last returned commit, queued when the walk continues
+    last_parents: Vec<Address>,
+}
+
+impl HistoryWalk {
+    pub fn new(commit_ids: Vec<Address>) -> HistoryWalk {
+        HistoryWalk {
+            queue: commit_ids.into_iter().collect(),
+            visited: HashSet::new(),
+            last_parents: vec![],
+        }
+    }
+
+    pub fn from_cursor(cursor: HistoryCursor) -> HistoryWalk {
+        HistoryWalk {
+            queue: cursor.queue.into_iter().collect(),
+            visited: cursor.visited.into_iter().collect(),
+            last_parents: vec![],
+        }
+    }
+
+    /**
+     * Returns the state of the walk, to resume it from the commit following the last returned one
+     */
+    pub fn cursor(&self) -> HistoryCursor {
+        HistoryCursor {
+            queue: self
+                .queue
+                .iter()
+                .chain(self.last_parents.iter())
+                .cloned()
+                .collect(),
+            visited: self.visited.iter().cloned().collect(),
+        }
+    }
+
+    pub fn next_commit(&mut self) -> ZomeApiResult<Option<HistoryCommit>> {
+        self.queue.extend(self.last_parents.drain(..));
+
+        while let Some(commit_id) = self.queue.pop_front() {
+            if let Some((internal_address, commit)) = commit::get_commit(&commit_id)? {
+                if self.visited.insert(internal_address.clone()) {
+                    self.last_parents = commit.commit_data().parentsIds.clone();
+
+                    return Ok(Some(HistoryCommit {
+                        id: commit_id,
+                        address: internal_address,
+                        commit,
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /**
+     * Skips the parents of the last returned commit, although they can still be visited
+     * through other paths
+     */
+    pub fn prune(&mut self) {
+        self.last_parents.clear();
+    }
+}
+
+/**
+ * Returns a page of the history of the given commit, in breadth-first order
+ *
+ * The walk resumes from the given cursor, returned by the previous page, so each page
+ * only fetches its own commits. There is no next cursor when no commits are left.
+ * Cursors hold the addresses of all the commits of the previous pages, so very long
+ * histories should be walked with large pages rather than many small ones.
+ */
+pub fn get_commit_history(
+    commit_id: Address,
+    limit: usize,
+    cursor: Option<HistoryCursor>,
+) -> ZomeApiResult<CommitHistory> {
+    if limit == 0 {
+        return Err(ZomeApiError::from(String::from(
+            "The limit of a history page must be greater than zero",
+        )));
+    }
+
+    let mut walk = match cursor {
+        Some(c) => HistoryWalk::from_cursor(c),
+        None => HistoryWalk::new(vec![commit_id]),
+    };
+
+    let mut commits: Vec<HistoryCommit> = Vec::new();
+    while commits.len() < limit {
+        match walk.next_commit()? {
+            Some(history_commit) => commits.push(history_commit),
+            None => {
+                return Ok(CommitHistory {
+                    commits,
+                    next_cursor: None,
+                })
+            }
+        }
+    }
+
+    // Taken before looking ahead, so that the next page starts with the commit found
+    let cursor = walk.cursor();
+    let next_cursor = walk.next_commit()?.map(|_| cursor);
+
+    Ok(CommitHistory {
+        commits,
+        next_cursor,
+    })
+}
+
+/**
+ * Returns the best common ancestors of the two given commits: the common ancestors
+ * which are not ancestors of any other common ancestor
+ *
+ * There is more than one best common ancestor when the histories of the commits
+ * have criss-cross merges
+ */
+pub fn find_merge_base(commit_a: Address, commit_b: Address) -> ZomeApiResult<Vec<Address>> {
+    let ancestors_a = get_ancestors(vec![commit_a])?;
+
+    // Common ancestors closest to commit_b
+    let mut candidates: Vec<HistoryCommit> = Vec::new();
+    let mut walk_b = HistoryWalk::new(vec![commit_b]);
+    while let Some(history_commit) = walk_b.next_commit()? {
+        if ancestors_a.contains(&history_commit.address) {
+            walk_b.prune();
+            candidates.push(history_commit);
+        }
+    }
+
+    // Discard the candidates reachable from other candidates
+    let candidates_parents = candidates
+        .iter()
+        .flat_map(|c| c.commit.commit_data().parentsIds.clone())
+        .collect();
+    let redundant = get_ancestors(candidates_parents)?;
+
+    Ok(candidates
+        .into_iter()
+        .filter(|c| !redundant.contains(&c.address))
+        .map(|c| c.id)
+        .collect())
+}
+
+/**
+ * Returns whether the first commit is an ancestor of the second one, or the same commit
+ */
+pub fn is_ancestor(ancestor: Address, descendant: Address) -> ZomeApiResult<bool> {
+    let ancestor_address = match commit::get_commit(&ancestor)? {
+        Some((internal_address, _)) => internal_address,
+        None => return Ok(false),
+    };
+
+    let mut walk = HistoryWalk::new(vec![descendant]);
+    while let Some(history_commit) = walk.next_commit()? {
+        if history_commit.address == ancestor_address {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/**
+ * Returns the internal addresses of the given commits and all their ancestors
+ */
+pub fn get_ancestors(commit_ids: Vec<Address>) -> ZomeApiResult<HashSet<Address>> {
+    let mut ancestors = HashSet::new();
+    let mut walk = HistoryWalk::new(commit_ids);
+
+    while let Some(history_commit) = walk.next_commit()? {
+        ancestors.insert(history_commit.address);
+    }
+
+    Ok(ancestors)
+}
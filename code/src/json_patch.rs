@@ -0,0 +1,244 @@
+//! JSON Patch (RFC 6902) between the data of commits
+//!
+//! Paths are JSON Pointers (RFC 6901). Diffs only contain add, remove and replace operations:
+//! objects are compared key by key and arrays index by index, adding or removing the
+//! trailing elements of the longer one. Patches can contain any of the six operations.
+
+use crate::utils::pointer_path;
+use crate::{data, perspective, perspective_details};
+use hdk::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/**
+ * Returns the patch which transforms the data of the first commit into the data of the second one
+ */
+pub fn diff_commits(from: Address, to: Address) -> ZomeApiResult<Vec<PatchOperation>> {
+    let from_data = data::get_commit_data(&from)?;
+    let to_data = data::get_commit_data(&to)?;
+
+    Ok(diff(&from_data, &to_data))
+}
+
+/**
+ * Applies the patch to the data of the base commit and commits the result on top of it,
+ * advancing the head of the perspective to the new commit
+ *
+ * Fails with a HeadConflict error if the head of the perspective is not the base commit
+ */
+pub fn commit_patch(
+    perspective_address: Address,
+    base_commit: Address,
+    patch: Vec<PatchOperation>,
+    message: String,
+    timestamp: u128,
+) -> ZomeApiResult<Address> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+    let base_data = data::get_commit_data(&base_commit)?;
+    let patched_data = apply(&base_data, &patch)?;
+
+    let saved = perspective_details::commit_on_head(
+        &internal_address,
+        Some(base_commit),
+        data::to_json_string(&patched_data),
+        message,
+        timestamp,
+    )?;
+
+    Ok(saved.commit_id)
+}
+
+/**
+ * Returns the patch which transforms the first value into the second one
+ */
+pub fn diff(from: &Value, to: &Value) -> Vec<PatchOperation> {
+    let mut operations = Vec::new();
+    diff_at(String::new(), from, to, &mut operations);
+    operations
+}
+
+/**
+ * Returns the result of applying all the operations of the patch to the given value,
+ * or the error of the first one that fails
+ */
+pub fn apply(value: &Value, patch: &Vec<PatchOperation>) -> ZomeApiResult<Value> {
+    let mut document = value.clone();
+
+    for operation in patch.iter() {
+        apply_operation(&mut document, operation.clone())
+            .map_err(|e| ZomeApiError::from(format!("Could not apply patch: {}", e)))?;
+    }
+
+    Ok(document)
+}
+
+/** Private helpers **/
+
+fn apply_operation(document: &mut Value, operation: PatchOperation) -> Result<(), String> {
+    match operation {
+        PatchOperation::Add { path, value } => add(document, &path, value),
+        PatchOperation::Remove { path } => remove(document, &path).map(|_| ()),
+        PatchOperation::Replace { path, value } => match document.pointer_mut(&path) {
+            Some(target) => {
+                *target = value;
+                Ok(())
+            }
+            None => Err(format!("{} does not exist", path)),
+        },
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(format!("Cannot move {} into one of its children", from));
+            }
+            let value = remove(document, &from)?;
+            add(document, &path, value)
+        }
+        PatchOperation::Copy { from, path } => match document.pointer(&from).cloned() {
+            Some(value) => add(document, &path, value),
+            None => Err(format!("{} does not exist", from)),
+        },
+        PatchOperation::Test { path, value } => match document.pointer(&path) {
+            Some(current) if *current == value => Ok(()),
+            _ => Err(format!("Test failed for {}", path)),
+        },
+    }
+}
+
+fn add(document: &mut Value, path: &String, value: Value) -> Result<(), String> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+
+    let (parent_path, key) = split_pointer(path)?;
+    match document.pointer_mut(parent_path) {
+        Some(Value::Object(map)) => {
+            map.insert(key, value);
+            Ok(())
+        }
+        Some(Value::Array(values)) => {
+            let index = match key.as_str() {
+                "-" => values.len(),
+                _ => parse_index(&key, values.len() + 1)?,
+            };
+            values.insert(index, value);
+            Ok(())
+        }
+        _ => Err(format!("{} is not an object or an array", parent_path)),
+    }
+}
+
+fn remove(document: &mut Value, path: &String) -> Result<Value, String> {
+    if path.is_empty() {
+        return Err(String::from("Cannot remove the whole document"));
+    }
+
+    let (parent_path, key) = split_pointer(path)?;
+    match document.pointer_mut(parent_path) {
+        Some(Value::Object(map)) => map
+            .remove(&key)
+            .ok_or_else(|| format!("{} does not exist", path)),
+        Some(Value::Array(values)) => {
+            let index = parse_index(&key, values.len())?;
+            Ok(values.remove(index))
+        }
+        _ => Err(format!("{} does not exist", path)),
+    }
+}
+
+/**
+ * Splits the pointer into the pointer of its parent and its last key, unescaped
+ */
+fn split_pointer(path: &String) -> Result<(&str, String), String> {
+    match path.rfind('/') {
+        Some(position) => Ok((
+            &path[..position],
+            path[position + 1..].replace("~1", "/").replace("~0", "~"),
+        )),
+        None => Err(format!("{} is not a valid JSON Pointer", path)),
+    }
+}
+
+/**
+ * Parses an array index, which must be lower than the given bound
+ */
+fn parse_index(key: &String, bound: usize) -> Result<usize, String> {
+    if key.is_empty() || (key.len() > 1 && key.starts_with('0')) {
+        return Err(format!("{} is not a valid array index", key));
+    }
+
+    match key.parse::<usize>() {
+        Ok(index) if index < bound => Ok(index),
+        Ok(_) => Err(format!("Array index {} is out of bounds", key)),
+        Err(_) => Err(format!("{} is not a valid array index", key)),
+    }
+}
+
+fn diff_at(path: String, from: &Value, to: &Value, operations: &mut Vec<PatchOperation>) {
+    if from == to {
+        return;
+    }
+
+    match (from, to) {
+        (Value::Object(from_map), Value::Object(to_map)) => {
+            // Sorted so that the patch doesn't depend on the order of the keys in the entries
+            let from_keys: BTreeSet<&String> = from_map.keys().collect();
+            let to_keys: BTreeSet<&String> = to_map.keys().collect();
+
+            for key in from_keys.iter() {
+                match to_map.get(*key) {
+                    Some(to_value) => {
+                        diff_at(pointer_path(&path, key), &from_map[*key], to_value, operations)
+                    }
+                    None => operations.push(PatchOperation::Remove {
+                        path: pointer_path(&path, key),
+                    }),
+                }
+            }
+            for key in to_keys.difference(&from_keys) {
+                operations.push(PatchOperation::Add {
+                    path: pointer_path(&path, key),
+                    value: to_map[*key].clone(),
+                });
+            }
+        }
+        (Value::Array(from_values), Value::Array(to_values)) => {
+            let common = from_values.len().min(to_values.len());
+
+            for index in 0..common {
+                diff_at(
+                    pointer_path(&path, &index.to_string()),
+                    &from_values[index],
+                    &to_values[index],
+                    operations,
+                );
+            }
+            // Removed from the end so the indexes of the remaining elements don't shift
+            for index in (common..from_values.len()).rev() {
+                operations.push(PatchOperation::Remove {
+                    path: pointer_path(&path, &index.to_string()),
+                });
+            }
+            for index in common..to_values.len() {
+                operations.push(PatchOperation::Add {
+                    path: pointer_path(&path, &index.to_string()),
+                    value: to_values[index].clone(),
+                });
+            }
+        }
+        _ => operations.push(PatchOperation::Replace {
+            path,
+            value: to.clone(),
+        }),
+    }
+}
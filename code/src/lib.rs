@@ -13,26 +13,38 @@ extern crate holochain_anchors;
 extern crate multihash;
 extern crate multibase;
 extern crate serde_cbor;
+extern crate secp256k1;
+extern crate tiny_keccak;
+extern crate hex;
+extern crate hcid;
 
 use hdk::holochain_persistence_api::cas::content::Address;
 use hdk::prelude::*;
 
 use hdk_proc_macros::zome;
 
+pub mod canonical;
 pub mod commit;
 pub mod context;
+pub mod history;
+pub mod json_patch;
+pub mod merge;
+pub mod permissions;
 pub mod perspective;
 pub mod perspective_details;
 pub mod proof;
+pub mod proposal;
 pub mod proxy;
 pub mod utils;
 pub mod data;
+pub mod verifiers;
 pub mod versioned_tags;
 
 #[zome]
 mod uprtcl {
 
     use perspective_details::PerspectiveDetails;
+    use versioned_tags::Version;
 
     #[init]
     fn init() {
@@ -61,11 +73,26 @@ mod uprtcl {
         commit::definition()
     }
 
+    #[entry_def]
+    fn commit_draft_entry_def() -> ValidatingEntryType {
+        commit::draft_definition()
+    }
+
     #[entry_def]
     fn perspective_entry_def() -> ValidatingEntryType {
         perspective::definition()
     }
 
+    #[entry_def]
+    fn invitation_entry_def() -> ValidatingEntryType {
+        permissions::definition()
+    }
+
+    #[entry_def]
+    fn proposal_entry_def() -> ValidatingEntryType {
+        proposal::definition()
+    }
+
     #[zome_fn("hc_public")]
     fn get_cas_id() -> ZomeApiResult<String> {
         Ok(utils::get_cas_id())
@@ -76,6 +103,11 @@ mod uprtcl {
         Ok(hdk::AGENT_ADDRESS.clone())
     }
 
+    #[zome_fn("hc_public")]
+    fn get_canonical_json(payload: JsonString) -> ZomeApiResult<String> {
+        canonical::to_canonical_json(payload)
+    }
+
     // Create entries
 
     #[zome_fn("hc_public")]
@@ -93,11 +125,86 @@ mod uprtcl {
         commit::create_commit(dataId, parentsIds, message, timestamp)
     }
 
+    #[zome_fn("hc_public")]
+    fn draft_commit(
+        dataId: Address,
+        parentsIds: Vec<Address>,
+        message: String,
+        timestamp: u128,
+        creatorsIds: Vec<Address>,
+    ) -> ZomeApiResult<commit::CommitDraftStatus> {
+        commit::draft_commit(dataId, parentsIds, message, timestamp, creatorsIds)
+    }
+
+    #[zome_fn("hc_public")]
+    fn sign_commit(draft_address: Address) -> ZomeApiResult<commit::CommitDraftStatus> {
+        commit::sign_commit(draft_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn merge_commits(
+        ours: Address,
+        theirs: Address,
+        message: String,
+        timestamp: u128,
+    ) -> ZomeApiResult<merge::MergeResult> {
+        merge::merge_commits(ours, theirs, message, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn cherry_pick(
+        perspective_address: Address,
+        commit_address: Address,
+        timestamp: u128,
+    ) -> ZomeApiResult<merge::MergeResult> {
+        merge::cherry_pick(perspective_address, commit_address, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn revert_commit(
+        perspective_address: Address,
+        commit_address: Address,
+        timestamp: u128,
+    ) -> ZomeApiResult<merge::MergeResult> {
+        merge::revert_commit(perspective_address, commit_address, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn commit_to_perspective(
+        perspective_address: Address,
+        data: JsonString,
+        message: String,
+        timestamp: u128,
+    ) -> ZomeApiResult<perspective_details::PerspectiveCommit> {
+        perspective_details::commit_to_perspective(perspective_address, data, message, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn commit_patch(
+        perspective_address: Address,
+        base_commit: Address,
+        patch: Vec<json_patch::PatchOperation>,
+        message: String,
+        timestamp: u128,
+    ) -> ZomeApiResult<Address> {
+        json_patch::commit_patch(perspective_address, base_commit, patch, message, timestamp)
+    }
+
     #[zome_fn("hc_public")]
     fn create_perspective(timestamp: u128) -> ZomeApiResult<Address> {
         perspective::create_perspective(timestamp)
     }
 
+    #[zome_fn("hc_public")]
+    fn fork_perspective(
+        source_address: Address,
+        name: String,
+        context: Option<String>,
+        timestamp: u128,
+    ) -> ZomeApiResult<Address> {
+        perspective::fork_perspective(source_address, name, context, timestamp)
+    }
+
     // Clone entries
 
     #[zome_fn("hc_public")]
@@ -113,7 +220,84 @@ mod uprtcl {
         previous_address: Option<Address>,
         perspective: perspective::Perspective,
     ) -> ZomeApiResult<Address> {
-        utils::clone_entry(previous_address, perspective)
+        perspective::clone_perspective(previous_address, perspective)
+    }
+
+    // Collaborators
+
+    #[zome_fn("hc_public")]
+    fn invite_collaborator(
+        perspective_address: Address,
+        agent_address: Address,
+        role: permissions::Role,
+        timestamp: u128,
+    ) -> ZomeApiResult<Address> {
+        permissions::invite_collaborator(perspective_address, agent_address, role, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_my_invitations() -> ZomeApiResult<Vec<Address>> {
+        permissions::get_my_invitations()
+    }
+
+    #[zome_fn("hc_public")]
+    fn accept_invite(invitation_address: Address) -> ZomeApiResult<()> {
+        permissions::accept_invite(invitation_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn revoke_collaborator(
+        perspective_address: Address,
+        agent_address: Address,
+    ) -> ZomeApiResult<()> {
+        permissions::revoke_collaborator(perspective_address, agent_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_permissions(perspective_address: Address) -> ZomeApiResult<permissions::Permissions> {
+        permissions::get_permissions(perspective_address)
+    }
+
+    // Proposals
+
+    #[zome_fn("hc_public")]
+    fn create_proposal(
+        to_perspective_address: Address,
+        from_perspective_address: Option<Address>,
+        updates: Vec<proposal::HeadUpdate>,
+        description: Option<String>,
+        timestamp: u128,
+    ) -> ZomeApiResult<Address> {
+        proposal::create_proposal(
+            to_perspective_address,
+            from_perspective_address,
+            updates,
+            description,
+            timestamp,
+        )
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_perspective_proposals(perspective_address: Address) -> ZomeApiResult<Vec<Address>> {
+        proposal::get_perspective_proposals(perspective_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_proposal_status(proposal_address: Address) -> ZomeApiResult<proposal::ProposalStatus> {
+        proposal::get_proposal_status(&proposal_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn update_proposal_status(
+        proposal_address: Address,
+        status: proposal::ProposalStatus,
+    ) -> ZomeApiResult<()> {
+        proposal::update_proposal_status(proposal_address, status)
+    }
+
+    #[zome_fn("hc_public")]
+    fn execute_proposal(proposal_address: Address) -> ZomeApiResult<()> {
+        proposal::execute_proposal(proposal_address)
     }
 
     // Getters
@@ -126,22 +310,143 @@ mod uprtcl {
         }
     }
 
+    #[zome_fn("hc_public")]
+    fn get_latest_draft(draft_address: Address) -> ZomeApiResult<commit::CommitDraftStatus> {
+        commit::get_latest_draft(draft_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_my_drafts() -> ZomeApiResult<Vec<Address>> {
+        commit::get_agent_drafts(&hdk::AGENT_ADDRESS)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_commit_history(
+        commit_id: Address,
+        limit: usize,
+        cursor: Option<history::HistoryCursor>,
+    ) -> ZomeApiResult<history::CommitHistory> {
+        history::get_commit_history(commit_id, limit, cursor)
+    }
+
+    #[zome_fn("hc_public")]
+    fn diff_commits(from: Address, to: Address) -> ZomeApiResult<Vec<json_patch::PatchOperation>> {
+        json_patch::diff_commits(from, to)
+    }
+
+    #[zome_fn("hc_public")]
+    fn find_merge_base(commit_a: Address, commit_b: Address) -> ZomeApiResult<Vec<Address>> {
+        history::find_merge_base(commit_a, commit_b)
+    }
+
+    #[zome_fn("hc_public")]
+    fn is_ancestor(ancestor: Address, descendant: Address) -> ZomeApiResult<bool> {
+        history::is_ancestor(ancestor, descendant)
+    }
+
     #[zome_fn("hc_public")]
     fn get_perspective_details(perspective_address: Address) -> ZomeApiResult<PerspectiveDetails> {
         perspective_details::get_perspective_details(perspective_address)
     }
 
+    #[zome_fn("hc_public")]
+    fn get_upstream_status(
+        perspective_address: Address,
+    ) -> ZomeApiResult<Option<perspective_details::UpstreamStatus>> {
+        perspective_details::get_upstream_status(perspective_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_perspective_heads(perspective_address: Address) -> ZomeApiResult<Vec<Address>> {
+        perspective_details::get_perspective_heads(perspective_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_perspective_head_history(
+        perspective_address: Address,
+        from_count: Option<usize>,
+        limit: usize,
+    ) -> ZomeApiResult<Vec<Version>> {
+        perspective_details::get_perspective_detail_history(
+            perspective_address,
+            "head",
+            from_count,
+            limit,
+        )
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_perspective_name_history(
+        perspective_address: Address,
+        from_count: Option<usize>,
+        limit: usize,
+    ) -> ZomeApiResult<Vec<Version>> {
+        perspective_details::get_perspective_detail_history(
+            perspective_address,
+            "name",
+            from_count,
+            limit,
+        )
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_perspective_context_history(
+        perspective_address: Address,
+        from_count: Option<usize>,
+        limit: usize,
+    ) -> ZomeApiResult<Vec<Version>> {
+        perspective_details::get_perspective_detail_history(
+            perspective_address,
+            "context",
+            from_count,
+            limit,
+        )
+    }
+
     #[zome_fn("hc_public")]
     fn get_context_perspectives(context: String) -> ZomeApiResult<Vec<Address>> {
         context::get_context_perspectives(context)
     }
 
+    #[zome_fn("hc_public")]
+    fn get_perspectives_by_name(name: String) -> ZomeApiResult<Vec<Address>> {
+        perspective_details::get_perspectives_by_name(name)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_agent_perspectives(
+        agent_address: Address,
+        context: Option<String>,
+    ) -> ZomeApiResult<Vec<Address>> {
+        perspective::get_agent_perspectives(&agent_address, context)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_my_perspectives(context: Option<String>) -> ZomeApiResult<Vec<Address>> {
+        perspective::get_agent_perspectives(&hdk::AGENT_ADDRESS, context)
+    }
+
     // Setters
     #[zome_fn("hc_public")]
     fn update_perspective_details(
         perspective_address: Address,
         details: PerspectiveDetails,
+        force: Option<bool>,
+        expected_head: Option<Address>,
     ) -> ZomeApiResult<()> {
-        perspective_details::update_perspective_details(perspective_address, details)
+        perspective_details::update_perspective_details(
+            perspective_address,
+            details,
+            force,
+            expected_head,
+        )
+        .map(|_| ())
+    }
+
+    #[zome_fn("hc_public")]
+    fn pull_perspective(
+        perspective_address: Address,
+    ) -> ZomeApiResult<perspective_details::PullResult> {
+        perspective_details::pull_perspective(perspective_address)
     }
 }
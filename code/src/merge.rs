@@ -0,0 +1,280 @@
+//! Structural three-way merge of the data of two commits against their merge base,
+//! also used to cherry-pick and revert the changes of single commits
+//!
+//! Objects are merged key by key, so changes to different keys of the same object never
+//! conflict. Any other value, including arrays, is replaced as a whole: it conflicts when
+//! both sides change it to different values.
+//!
+//! Conflicts are reported by their JSON Pointer (RFC 6901) path, the empty path being the root.
+
+use crate::commit::{self, Commit};
+use crate::data::{self, get_commit_data, to_json_string};
+use crate::utils::pointer_path;
+use crate::{history, perspective, perspective_details};
+use hdk::prelude::*;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/**
+ * Value which both sides changed in different ways, None meaning that the key was removed
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeConflict {
+    pub path: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MergeResult {
+    // Merge commit, only created when there are no conflicts
+    pub commit: Option<Address>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/**
+ * Merges the data of the given commits and creates a commit with both of them as parents,
+ * or returns the conflicts without creating anything
+ *
+ * Commits without common ancestors are merged against empty data. When they have more than
+ * one best common ancestor, after criss-cross merges, there is no single base to merge against,
+ * so their whole data is reported as a conflict at the root path, without base
+ */
+pub fn merge_commits(
+    ours: Address,
+    theirs: Address,
+    message: String,
+    timestamp: u128,
+) -> ZomeApiResult<MergeResult> {
+    let ours_data = get_commit_data(&ours)?;
+    let theirs_data = get_commit_data(&theirs)?;
+
+    let bases = history::find_merge_base(ours.clone(), theirs.clone())?;
+    let base_data = match bases.as_slice() {
+        [] => Value::Object(Map::new()),
+        [base] => get_commit_data(base)?,
+        _ => {
+            return Ok(MergeResult {
+                commit: None,
+                conflicts: vec![MergeConflict {
+                    path: String::new(),
+                    base: None,
+                    ours: Some(ours_data),
+                    theirs: Some(theirs_data),
+                }],
+            })
+        }
+    };
+
+    match merge(&base_data, &ours_data, &theirs_data) {
+        Ok(merged) => {
+            let data_id = data::create_data(to_json_string(&merged), None)?;
+            let commit_address =
+                commit::create_commit(data_id, vec![ours, theirs], message, timestamp)?;
+
+            Ok(MergeResult {
+                commit: Some(commit_address),
+                conflicts: vec![],
+            })
+        }
+        Err(conflicts) => Ok(MergeResult {
+            commit: None,
+            conflicts,
+        }),
+    }
+}
+
+/**
+ * Three-way merge of the given values, returning all the conflicts if there is any
+ */
+pub fn merge(base: &Value, ours: &Value, theirs: &Value) -> Result<Value, Vec<MergeConflict>> {
+    let mut conflicts = Vec::new();
+    let merged = merge_at(
+        String::new(),
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &mut conflicts,
+    );
+
+    match conflicts.is_empty() {
+        true => Ok(merged.unwrap_or(Value::Null)),
+        false => Err(conflicts),
+    }
+}
+
+/**
+ * Applies the changes introduced by the given commit relative to its first parent
+ * on top of the head of the perspective, which advances to the new commit
+ */
+pub fn cherry_pick(
+    perspective_address: Address,
+    commit_address: Address,
+    timestamp: u128,
+) -> ZomeApiResult<MergeResult> {
+    let commit = find_commit(&commit_address)?;
+    let parent_data = get_first_parent_data(&commit)?;
+
+    let message = format!(
+        "{}\n\nCherry-picked-from: {}",
+        commit.commit_data().message,
+        commit_address
+    );
+
+    apply_change(
+        perspective_address,
+        &parent_data,
+        &data::get_data_value(&commit.commit_data().dataId)?,
+        message,
+        timestamp,
+    )
+}
+
+/**
+ * Undoes the changes introduced by the given commit relative to its first parent
+ * on top of the head of the perspective, which advances to the new commit
+ */
+pub fn revert_commit(
+    perspective_address: Address,
+    commit_address: Address,
+    timestamp: u128,
+) -> ZomeApiResult<MergeResult> {
+    let commit = find_commit(&commit_address)?;
+    let parent_data = get_first_parent_data(&commit)?;
+
+    let subject = commit.commit_data().message.lines().next().unwrap_or("");
+    let message = format!("Revert \"{}\"\n\nReverts: {}", subject, commit_address);
+
+    apply_change(
+        perspective_address,
+        &data::get_data_value(&commit.commit_data().dataId)?,
+        &parent_data,
+        message,
+        timestamp,
+    )
+}
+
+/** Private helpers **/
+
+fn find_commit(commit_address: &Address) -> ZomeApiResult<Commit> {
+    match commit::get_commit(commit_address)? {
+        Some((_, commit)) => Ok(commit),
+        None => Err(ZomeApiError::from(format!(
+            "Could not find commit {}",
+            commit_address
+        ))),
+    }
+}
+
+/**
+ * Returns the data of the first parent of the commit, or empty data for root commits
+ */
+fn get_first_parent_data(commit: &Commit) -> ZomeApiResult<Value> {
+    match commit.commit_data().parentsIds.get(0) {
+        Some(parent) => get_commit_data(parent),
+        None => Ok(Value::Object(Map::new())),
+    }
+}
+
+/**
+ * Merges the change from one value to another into the data of the head of the perspective,
+ * committing the result on top of the head when there are no conflicts
+ */
+fn apply_change(
+    perspective_address: Address,
+    from: &Value,
+    to: &Value,
+    message: String,
+    timestamp: u128,
+) -> ZomeApiResult<MergeResult> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+    let head = match perspective_details::get_perspective_head(&internal_address)? {
+        Some(head) => head,
+        None => {
+            return Err(ZomeApiError::from(String::from(
+                "The perspective has no head to apply the commit on",
+            )))
+        }
+    };
+
+    match merge(from, &get_commit_data(&head)?, to) {
+        Ok(merged) => {
+            let saved = perspective_details::commit_on_head(
+                &internal_address,
+                Some(head),
+                to_json_string(&merged),
+                message,
+                timestamp,
+            )?;
+
+            Ok(MergeResult {
+                commit: Some(saved.commit_id),
+                conflicts: vec![],
+            })
+        }
+        Err(conflicts) => Ok(MergeResult {
+            commit: None,
+            conflicts,
+        }),
+    }
+}
+
+fn merge_at(
+    path: String,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+
+    match (base, ours, theirs) {
+        (Some(Value::Object(b)), Some(Value::Object(o)), Some(Value::Object(t))) => {
+            Some(Value::Object(merge_objects(path, b, o, t, conflicts)))
+        }
+        // Keys added on both sides are merged as if they were empty objects before
+        (None, Some(Value::Object(o)), Some(Value::Object(t))) => Some(Value::Object(
+            merge_objects(path, &Map::new(), o, t, conflicts),
+        )),
+        _ => {
+            conflicts.push(MergeConflict {
+                path,
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+fn merge_objects(
+    path: String,
+    base: &Map<String, Value>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Map<String, Value> {
+    let keys: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+
+    let mut merged = Map::new();
+    for key in keys {
+        let value = merge_at(
+            pointer_path(&path, key),
+            base.get(key),
+            ours.get(key),
+            theirs.get(key),
+            conflicts,
+        );
+        if let Some(v) = value {
+            merged.insert(key.clone(), v);
+        }
+    }
+    merged
+}
@@ -0,0 +1,362 @@
+use crate::utils::split_link_validation_data;
+use crate::perspective::{self, Perspective};
+use hdk::holochain_core_types::validation::ValidationData;
+use hdk::prelude::*;
+use hdk::AGENT_ADDRESS;
+
+/**
+ * Roles of the agents on a perspective, each one including the permissions of the previous ones
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Reader,
+    Writer,
+    Admin,
+    Owner,
+}
+
+/**
+ * Invitation for an agent to collaborate on a perspective, which grants the role
+ * once the invited agent accepts it
+ */
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Invitation {
+    pub perspectiveId: Address,
+    pub agentId: Address,
+    pub role: Role,
+    pub inviterId: Address,
+    pub timestamp: u128,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Collaborator {
+    pub agent_id: Address,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Permissions {
+    pub owner: Address,
+    // Agent acting as owner of perspectives created on other platforms
+    pub custodian: Option<Address>,
+    pub collaborators: Vec<Collaborator>,
+}
+
+pub fn definition() -> ValidatingEntryType {
+    entry!(
+        name: "invitation",
+        description: "invitation for an agent to collaborate on a perspective",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: |validation_data: hdk::EntryValidationData<Invitation>| {
+            match validation_data {
+                EntryValidationData::Create { entry, validation_data } => {
+                    validate_invitation(&entry, &validation_data)
+                },
+                _ => Err("Cannot modify or delete invitations".into())
+            }
+        },
+        links: [
+            from!(
+                "perspective",
+                link_type: "invitation",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, validation_data) = split_link_validation_data(validation_data);
+                    let invitation: Invitation =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    validate_role(&invitation.perspectiveId, &validation_data, Role::Admin)
+                }
+            ),
+            from!(
+                "perspective",
+                link_type: "collaborator",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_collaborator_link(validation_data)
+                }
+            ),
+            from!(
+                "%agent_id",
+                link_type: "agent->invitation",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, validation_data) = split_link_validation_data(validation_data);
+                    let invitation: Invitation =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    match validation_data.sources().contains(&invitation.inviterId) {
+                        true => Ok(()),
+                        false => Err(String::from(
+                            "Only the inviter can link the invitation to the agent",
+                        )),
+                    }
+                }
+            )
+        ]
+    )
+}
+
+/**
+ * Returns the role of the given agent on the given perspective, if any
+ */
+pub fn get_agent_role(
+    perspective_address: &Address,
+    agent_address: &Address,
+) -> ZomeApiResult<Option<Role>> {
+    let perspective: Perspective = hdk::utils::get_as_type(perspective_address.clone())?;
+
+    if perspective.creator_id() == agent_address.clone() {
+        return Ok(Some(Role::Owner));
+    }
+    if perspective.has_external_creator()
+        && perspective::get_custodian(perspective_address)? == Some(agent_address.clone())
+    {
+        return Ok(Some(Role::Owner));
+    }
+
+    Ok(get_collaborations(perspective_address)?
+        .into_iter()
+        .filter(|invitation| invitation.agentId == agent_address.clone())
+        .map(|invitation| invitation.role)
+        .max())
+}
+
+/**
+ * Checks that one of the authors of the validated entry or link has at least the given role
+ * on the given perspective
+ */
+pub fn validate_role(
+    perspective_address: &Address,
+    validation_data: &ValidationData,
+    minimum_role: Role,
+) -> Result<(), String> {
+    for source in validation_data.sources().iter() {
+        if let Some(role) = get_agent_role(perspective_address, source)? {
+            if role >= minimum_role {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(format!(
+        "Only agents with at least the {:?} role can perform this action",
+        minimum_role
+    ))
+}
+
+// Public handlers
+
+/**
+ * Invite the given agent to collaborate on the perspective with the given role
+ */
+pub fn invite_collaborator(
+    perspective_address: Address,
+    agent_address: Address,
+    role: Role,
+    timestamp: u128,
+) -> ZomeApiResult<Address> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    let invitation = Invitation {
+        perspectiveId: internal_address.clone(),
+        agentId: agent_address.clone(),
+        role,
+        inviterId: AGENT_ADDRESS.clone(),
+        timestamp,
+    };
+    let invitation_address = hdk::commit_entry(&invitation_entry(&invitation))?;
+
+    hdk::link_entries(&internal_address, &invitation_address, "invitation", "")?;
+    hdk::link_entries(&agent_address, &invitation_address, "agent->invitation", "")?;
+
+    Ok(invitation_address)
+}
+
+/**
+ * Returns the addresses of the invitations to collaborate received by the calling agent
+ */
+pub fn get_my_invitations() -> ZomeApiResult<Vec<Address>> {
+    let links = hdk::get_links(
+        &AGENT_ADDRESS,
+        LinkMatch::Exactly("agent->invitation"),
+        LinkMatch::Any,
+    )?;
+
+    Ok(links.addresses())
+}
+
+/**
+ * Accept the given invitation, which grants its role to the calling agent
+ */
+pub fn accept_invite(invitation_address: Address) -> ZomeApiResult<()> {
+    let invitation: Invitation = hdk::utils::get_as_type(invitation_address.clone())?;
+
+    if invitation.agentId != AGENT_ADDRESS.clone() {
+        return Err(ZomeApiError::from(String::from(
+            "Only the invited agent can accept the invitation",
+        )));
+    }
+
+    hdk::link_entries(
+        &invitation.perspectiveId,
+        &invitation_address,
+        "collaborator",
+        "",
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Revoke all the roles and pending invitations of the given agent on the perspective
+ */
+pub fn revoke_collaborator(
+    perspective_address: Address,
+    agent_address: Address,
+) -> ZomeApiResult<()> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    for link_type in vec!["collaborator", "invitation"] {
+        let links = hdk::get_links(
+            &internal_address,
+            LinkMatch::Exactly(link_type),
+            LinkMatch::Any,
+        )?;
+
+        for invitation_address in links.addresses() {
+            let invitation: Invitation = hdk::utils::get_as_type(invitation_address.clone())?;
+
+            if invitation.agentId == agent_address {
+                hdk::remove_link(&internal_address, &invitation_address, link_type, "")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Returns the owner of the perspective and the roles of its collaborators
+ */
+pub fn get_permissions(perspective_address: Address) -> ZomeApiResult<Permissions> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+    let perspective: Perspective = hdk::utils::get_as_type(internal_address.clone())?;
+
+    let collaborators = get_collaborations(&internal_address)?
+        .into_iter()
+        .map(|invitation| Collaborator {
+            agent_id: invitation.agentId,
+            role: invitation.role,
+        })
+        .collect();
+
+    let custodian = match perspective.has_external_creator() {
+        true => perspective::get_custodian(&internal_address)?,
+        false => None,
+    };
+
+    Ok(Permissions {
+        owner: perspective.creator_id(),
+        custodian,
+        collaborators,
+    })
+}
+
+/** Private helpers **/
+
+fn invitation_entry(invitation: &Invitation) -> Entry {
+    Entry::App("invitation".into(), invitation.into())
+}
+
+/**
+ * Returns the accepted invitations of the given perspective
+ */
+fn get_collaborations(perspective_address: &Address) -> ZomeApiResult<Vec<Invitation>> {
+    let links = hdk::get_links(
+        &perspective_address,
+        LinkMatch::Exactly("collaborator"),
+        LinkMatch::Any,
+    )?;
+
+    links
+        .addresses()
+        .into_iter()
+        .map(|invitation_address| hdk::utils::get_as_type(invitation_address))
+        .collect()
+}
+
+fn validate_invitation(
+    invitation: &Invitation,
+    validation_data: &ValidationData,
+) -> Result<(), String> {
+    if invitation.role == Role::Owner {
+        return Err(String::from("Perspectives can only have one owner"));
+    }
+    if !validation_data.sources().contains(&invitation.inviterId) {
+        return Err(String::from("The author of the invitation must be the inviter"));
+    }
+
+    match get_agent_role(&invitation.perspectiveId, &invitation.inviterId)? {
+        Some(ref role) if *role >= Role::Admin && *role >= invitation.role => Ok(()),
+        _ => Err(String::from(
+            "Only admins can invite collaborators, with at most their own role",
+        )),
+    }
+}
+
+/**
+ * Collaborators are added by the invited agent while the invitation is not revoked,
+ * and removed by themselves or by an admin
+ */
+fn validate_collaborator_link(validation_data: hdk::LinkValidationData) -> Result<(), String> {
+    match validation_data {
+        hdk::LinkValidationData::LinkAdd {
+            link,
+            validation_data,
+        } => {
+            let invitation: Invitation = hdk::utils::get_as_type(link.link().target().clone())?;
+
+            if link.link().base().clone() != invitation.perspectiveId {
+                return Err(String::from("The invitation is for another perspective"));
+            }
+
+            // Revoked invitations are no longer linked from the perspective
+            let pending_invitations = hdk::get_links(
+                &invitation.perspectiveId,
+                LinkMatch::Exactly("invitation"),
+                LinkMatch::Any,
+            )?;
+            if !pending_invitations.addresses().contains(link.link().target()) {
+                return Err(String::from("The invitation has been revoked"));
+            }
+            match validation_data.sources().contains(&invitation.agentId) {
+                true => Ok(()),
+                false => Err(String::from(
+                    "Only the invited agent can accept the invitation",
+                )),
+            }
+        }
+        hdk::LinkValidationData::LinkRemove {
+            link,
+            validation_data,
+        } => {
+            let invitation: Invitation = hdk::utils::get_as_type(link.link().target().clone())?;
+
+            match validation_data.sources().contains(&invitation.agentId) {
+                true => Ok(()),
+                false => validate_role(&invitation.perspectiveId, &validation_data, Role::Admin),
+            }
+        }
+    }
+}
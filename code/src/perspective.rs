@@ -1,9 +1,13 @@
-use crate::proof::{Proof, Secured};
-use crate::utils;
+use crate::permissions::{self, Role};
+use crate::proof::{Proof, ProofType, Secured};
+use crate::perspective_details::{self, PerspectiveDetails};
+use crate::{context, proxy, utils, verifiers, versioned_tags};
 use hdk::{
     entry_definition::ValidatingEntryType,
-    error::ZomeApiResult,
-    holochain_core_types::{dna::entry_types::Sharing, entry::Entry},
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::ValidationData,
+    },
     holochain_json_api::{error::JsonError, json::JsonString},
     holochain_persistence_api::cas::content::Address,
 };
@@ -34,6 +38,17 @@ impl Perspective {
 
         Perspective::from_data(perspective_data)
     }
+
+    pub fn creator_id(&self) -> Address {
+        self.payload.creatorId.to_owned()
+    }
+
+    /**
+     * Returns whether the creator is not a holochain agent, and so cannot act in this app
+     */
+    pub fn has_external_creator(&self) -> bool {
+        verifiers::proof_type_for(&self.payload.creatorId) != ProofType::Ed25519
+    }
 }
 
 impl Secured<PerspectiveData> for Perspective {
@@ -50,16 +65,16 @@ impl Secured<PerspectiveData> for Perspective {
         Entry::App("perspective".into(), self.into())
     }
 
-    fn creator_id(&self) -> Address {
-        self.payload.creatorId.to_owned()
+    fn creators_ids(&self) -> Vec<Address> {
+        vec![self.payload.creatorId.to_owned()]
     }
 
     fn payload(&self) -> JsonString {
         self.payload.to_owned().into()
     }
 
-    fn proof(&self) -> Proof {
-        self.proof.to_owned()
+    fn proofs(&self) -> Vec<Proof> {
+        vec![self.proof.to_owned()]
     }
 }
 
@@ -73,8 +88,8 @@ pub fn definition() -> ValidatingEntryType {
         },
         validation: |validation_data: hdk::EntryValidationData<Perspective>| {
             match validation_data {
-                hdk::EntryValidationData::Create { .. } => {
-                    Ok(())
+                hdk::EntryValidationData::Create { entry, .. } => {
+                    Proof::verify(entry)
                 },
                 _ => Err("Cannot modify or delete perspectives".into())
             }
@@ -86,8 +101,8 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: |_validation_data: hdk::LinkValidationData | {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_owner_link(validation_data)
                 }
             ),
             to!(
@@ -96,8 +111,8 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: |_validation_data: hdk::LinkValidationData | {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_owner_link(validation_data)
                 }
             ),
             to!(
@@ -106,8 +121,28 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: |_validation_data: hdk::LinkValidationData | {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_owner_link(validation_data)
+                }
+            ),
+            to!(
+                holochain_anchors::ANCHOR_TYPE,
+                link_type: "fast_forward_only",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_admin_link(validation_data)
+                }
+            ),
+            to!(
+                "perspective",
+                link_type: "upstream",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_admin_link(validation_data)
                 }
             ),
             from!(
@@ -116,8 +151,18 @@ pub fn definition() -> ValidatingEntryType {
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: |_validation_data: hdk::LinkValidationData | {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_owner_link_to(validation_data)
+                }
+            ),
+            from!(
+                holochain_anchors::ANCHOR_TYPE,
+                link_type: "name->perspective",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    validate_owner_link_to(validation_data)
                 }
             ),
             from!(
@@ -130,20 +175,75 @@ pub fn definition() -> ValidatingEntryType {
                     Ok(())
                 }
             ),
+            to!(
+                "%agent_id",
+                link_type: "custodian",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, validation_data) =
+                        utils::split_link_validation_data(validation_data);
+                    let perspective: Perspective =
+                        hdk::utils::get_as_type(link_data.link().base().clone())?;
+
+                    if !perspective.has_external_creator() {
+                        return Err("Only perspectives from other platforms have custodians".into());
+                    }
+                    match validation_data.sources().contains(link_data.link().target()) {
+                        true => Ok(()),
+                        false => Err("Agents can only claim custody for themselves".into()),
+                    }
+                }
+            ),
             from!(
                 "%agent_id",
                 link_type: "agent->perspective",
                 validation_package: || {
                     hdk::ValidationPackageDefinition::Entry
                 },
-                validation: |_validation_data: hdk::LinkValidationData | {
-                    Ok(())
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, _) = utils::split_link_validation_data(validation_data);
+                    let perspective: Perspective =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    match link_data.link().base().clone() == perspective.creator_id() {
+                        true => Ok(()),
+                        false => Err("Perspectives can only be linked from their creator".into()),
+                    }
                 }
             )
         ]
     )
 }
 
+/**
+ * Checks that the author of a link from a perspective can write on it
+ */
+pub fn validate_owner_link(validation_data: hdk::LinkValidationData) -> Result<(), String> {
+    let (link_data, validation_data) = utils::split_link_validation_data(validation_data);
+
+    validate_perspective_author(link_data.link().base(), &validation_data, Role::Writer)
+}
+
+/**
+ * Checks that the author of a link from a perspective can change its settings
+ */
+pub fn validate_admin_link(validation_data: hdk::LinkValidationData) -> Result<(), String> {
+    let (link_data, validation_data) = utils::split_link_validation_data(validation_data);
+
+    validate_perspective_author(link_data.link().base(), &validation_data, Role::Admin)
+}
+
+/**
+ * Checks that the author of a link to a perspective can write on it
+ */
+pub fn validate_owner_link_to(validation_data: hdk::LinkValidationData) -> Result<(), String> {
+    let (link_data, validation_data) = utils::split_link_validation_data(validation_data);
+
+    validate_perspective_author(link_data.link().target(), &validation_data, Role::Writer)
+}
+
 // Create
 
 /**
@@ -151,8 +251,131 @@ pub fn definition() -> ValidatingEntryType {
  */
 pub fn create_perspective(timestamp: u128) -> ZomeApiResult<Address> {
     let perspective = Perspective::new(timestamp)?;
+    let creator_id = perspective.creator_id();
+
+    let perspective_address = utils::create_entry(perspective)?;
+    hdk::link_entries(&creator_id, &perspective_address, "agent->perspective", "")?;
+
+    Ok(perspective_address)
+}
+
+/**
+ * Clone the given perspective, linking it from its creator if it's an agent of this app
+ *
+ * Creators from other platforms cannot act in this app, so the first agent to clone their
+ * perspectives becomes their custodian, with the same permissions as an owner
+ */
+pub fn clone_perspective(
+    previous_address: Option<Address>,
+    perspective: Perspective,
+) -> ZomeApiResult<Address> {
+    let creator_id = perspective.creator_id();
+    let external_creator = perspective.has_external_creator();
+    let perspective_address = utils::clone_entry(previous_address, perspective)?;
+
+    if let Some(Entry::AgentId(_)) = hdk::get_entry(&creator_id)? {
+        hdk::link_entries(&creator_id, &perspective_address, "agent->perspective", "")?;
+    } else if external_creator && get_custodian(&perspective_address)?.is_none() {
+        versioned_tags::link_with_content(
+            &perspective_address,
+            &AGENT_ADDRESS,
+            "custodian".into(),
+            AGENT_ADDRESS.clone(),
+        )?;
+    }
+
+    Ok(perspective_address)
+}
+
+/**
+ * Create a perspective with the head and context of the source one, tracking it as its upstream
+ */
+pub fn fork_perspective(
+    source_address: Address,
+    name: String,
+    context: Option<String>,
+    timestamp: u128,
+) -> ZomeApiResult<Address> {
+    let source_id = internal_perspective_address(&source_address)?;
+    let source_details = perspective_details::get_perspective_details(source_id.clone())?;
+
+    let perspective_address = create_perspective(timestamp)?;
+
+    let details = PerspectiveDetails {
+        name: Some(name),
+        context: context.or(source_details.context),
+        head: source_details.head,
+        fast_forward_only: None,
+        upstream: Some(source_id),
+    };
+    perspective_details::update_perspective_details(
+        perspective_address.clone(),
+        details,
+        None,
+        None,
+    )?;
 
-    utils::create_entry(perspective)
+    Ok(perspective_address)
 }
 
 // Getters
+
+/**
+ * Returns the address of the given perspective in this app, resolving proxies
+ */
+pub fn internal_perspective_address(perspective_address: &Address) -> ZomeApiResult<Address> {
+    match proxy::internal_address(&perspective_address)? {
+        Some(address) => Ok(address),
+        None => Err(ZomeApiError::from(String::from(
+            "Could not find given perspective",
+        ))),
+    }
+}
+
+/**
+ * Returns the agent acting as owner of a perspective created on another platform,
+ * which is the first one that cloned it
+ */
+pub fn get_custodian(perspective_address: &Address) -> ZomeApiResult<Option<Address>> {
+    let claims = versioned_tags::get_history(perspective_address, "custodian".into(), None, 1)?;
+
+    Ok(claims.into_iter().next().and_then(|claim| claim.author))
+}
+
+/**
+ * Returns the perspectives created by the given agent, optionally only those with the given context
+ */
+pub fn get_agent_perspectives(
+    agent_address: &Address,
+    context: Option<String>,
+) -> ZomeApiResult<Vec<Address>> {
+    let links = hdk::get_links(
+        agent_address,
+        LinkMatch::Exactly("agent->perspective"),
+        LinkMatch::Any,
+    )?;
+
+    let context = match context {
+        Some(c) => c,
+        None => return Ok(links.addresses()),
+    };
+
+    let mut perspectives = Vec::new();
+    for perspective_address in links.addresses() {
+        if context::get_perspective_context(&perspective_address)? == Some(context.clone()) {
+            perspectives.push(perspective_address);
+        }
+    }
+
+    Ok(perspectives)
+}
+
+/** Private helpers **/
+
+fn validate_perspective_author(
+    perspective_address: &Address,
+    validation_data: &ValidationData,
+    min_role: Role,
+) -> Result<(), String> {
+    permissions::validate_role(perspective_address, validation_data, min_role)
+}
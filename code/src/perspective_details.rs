@@ -1,41 +1,108 @@
-use crate::{context, proxy, versioned_tags};
+use crate::permissions::{self, Role};
+use crate::versioned_tags::{self, Version};
+use crate::{commit, context, data, history, perspective, proxy, utils};
 use hdk::prelude::*;
+use hdk::AGENT_ADDRESS;
 
-#[derive(Serialize, Deserialize, Debug, self::DefaultJson, Clone)]
+// Prefix of the error returned when the head of a perspective is not the expected one
+pub const HEAD_CONFLICT_ERROR: &str = "HeadConflict";
+
+#[derive(Serialize, Deserialize, Debug, self::DefaultJson, Clone, Default)]
 pub struct PerspectiveDetails {
     pub name: Option<String>,
     pub context: Option<String>,
     pub head: Option<Address>,
+    // Only allow head updates that descend from the current head
+    pub fast_forward_only: Option<bool>,
+    // Perspective whose changes this perspective follows
+    pub upstream: Option<Address>,
+}
+
+/**
+ * Entries created when saving data to a perspective, and the version of its new head
+ */
+#[derive(Serialize, Deserialize, Debug, self::DefaultJson, Clone)]
+pub struct PerspectiveCommit {
+    pub data_id: Address,
+    pub commit_id: Address,
+    pub head_version: usize,
+}
+
+/**
+ * Number of commits in the history of each head which are not in the history of the other
+ */
+#[derive(Serialize, Deserialize, Debug, self::DefaultJson, Clone)]
+pub struct UpstreamStatus {
+    pub upstream: Address,
+    pub head: Option<Address>,
+    pub upstream_head: Option<Address>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStatus {
+    UpToDate,
+    FastForwarded,
+    // Both heads have new commits and must be merged by the client
+    Diverged,
+}
+
+#[derive(Serialize, Deserialize, Debug, self::DefaultJson, Clone)]
+pub struct PullResult {
+    pub status: PullStatus,
+    pub head: Option<Address>,
+    pub upstream_head: Option<Address>,
+    // Best common ancestors of both heads, only when they have diverged
+    pub merge_base: Vec<Address>,
 }
 
 pub fn get_perspective_details(perspective_address: Address) -> ZomeApiResult<PerspectiveDetails> {
-    let internal_address = match proxy::internal_address(&perspective_address)? {
-        Some(address) => Ok(address),
-        None => Err(ZomeApiError::from(String::from("Could not find given perspective")))
-    }?;
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
 
     let head = get_perspective_head(&internal_address)?;
     let name = get_perspective_name(&internal_address)?;
     let context = context::get_perspective_context(&internal_address)?;
+    let fast_forward_only = get_fast_forward_only_setting(&internal_address)?;
+    let upstream = get_perspective_upstream(&internal_address)?;
 
     Ok(PerspectiveDetails {
         head,
         context,
         name,
+        fast_forward_only,
+        upstream,
     })
 }
 
+/**
+ * Updates the given details of the perspective, where a new head must be a fast-forward
+ * of the current one if the perspective requires it, unless forced
+ *
+ * If an expected head is given, fails with a HeadConflict error without updating anything
+ * when the current head of the perspective is a different one
+ *
+ * Returns the version of the new head, if the head was updated
+ */
 pub fn update_perspective_details(
     perspective_address: Address,
     details: PerspectiveDetails,
-) -> ZomeApiResult<()> {
-    let internal_address = match proxy::internal_address(&perspective_address)? {
-        Some(address) => Ok(address),
-        None => Err(ZomeApiError::from(String::from("Could not find given perspective")))
-    }?;
+    force: Option<bool>,
+    expected_head: Option<Address>,
+) -> ZomeApiResult<Option<usize>> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
 
+    if expected_head.is_some() {
+        check_expected_head(&internal_address, &expected_head)?;
+    }
+
+    let mut head_version = None;
     if let Some(head_address) = details.head {
-        update_perspective_head(&internal_address, &head_address)?;
+        if !force.unwrap_or(false) {
+            check_fast_forward(&internal_address, &head_address)?;
+        }
+        head_version = Some(update_perspective_head(&internal_address, &head_address)?);
     }
     if let Some(context) = details.context {
         context::update_perspective_context(&internal_address, context)?;
@@ -43,17 +110,91 @@ pub fn update_perspective_details(
     if let Some(name) = details.name {
         update_perspective_name(&internal_address, name)?;
     }
+    if let Some(fast_forward_only) = details.fast_forward_only {
+        // Skipped when unchanged, so that saving the details as they were read adds no versions
+        if get_fast_forward_only_setting(&internal_address)? != Some(fast_forward_only) {
+            update_fast_forward_only(&internal_address, fast_forward_only)?;
+        }
+    }
+    if let Some(upstream) = details.upstream {
+        let upstream_address = perspective::internal_perspective_address(&upstream)?;
 
-    Ok(())
+        // Skipped when unchanged, like fast_forward_only
+        if get_perspective_upstream(&internal_address)? != Some(upstream_address.clone()) {
+            update_perspective_upstream(&internal_address, &upstream_address)?;
+        }
+    }
+
+    Ok(head_version)
+}
+
+/**
+ * Saves the data in a new commit on top of the current head of the perspective,
+ * which then becomes its head
+ */
+pub fn commit_to_perspective(
+    perspective_address: Address,
+    data: JsonString,
+    message: String,
+    timestamp: u128,
+) -> ZomeApiResult<PerspectiveCommit> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+    let head = get_perspective_head(&internal_address)?;
+
+    commit_on_head(&internal_address, head, data, message, timestamp)
+}
+
+/**
+ * Saves the data in a new commit with the given head as parent, and makes it the head
+ * of the perspective, failing with a HeadConflict error if the head is not the given one,
+ * or if the perspective has a head when None is given
+ *
+ * Everything that can make the update fail is checked before creating any entry, so that
+ * no orphan data or commits are left behind. The head can still be updated concurrently
+ * by another agent after the check, in which case both heads compete for the same version.
+ */
+pub fn commit_on_head(
+    perspective_address: &Address,
+    head: Option<Address>,
+    data: JsonString,
+    message: String,
+    timestamp: u128,
+) -> ZomeApiResult<PerspectiveCommit> {
+    match permissions::get_agent_role(perspective_address, &AGENT_ADDRESS)? {
+        Some(ref role) if *role >= Role::Writer => (),
+        _ => {
+            return Err(ZomeApiError::from(String::from(
+                "Only agents with at least the Writer role can update the perspective",
+            )))
+        }
+    }
+    check_expected_head(perspective_address, &head)?;
+
+    let data_id = data::create_data(data, None)?;
+    let commit_id = commit::create_commit(
+        data_id.clone(),
+        head.into_iter().collect(),
+        message,
+        timestamp,
+    )?;
+
+    // The new commit descends from the current head, so it's always a fast-forward
+    let head_version = update_perspective_head(perspective_address, &commit_id)?;
+
+    Ok(PerspectiveCommit {
+        data_id,
+        commit_id,
+        head_version,
+    })
 }
 
 /**
- * Updates the head commit associated with the given perspective
+ * Updates the head commit associated with the given perspective, returning its version
  */
 pub fn update_perspective_head(
     perspective_address: &Address,
     head_address: &Address,
-) -> ZomeApiResult<()> {
+) -> ZomeApiResult<usize> {
     let proxy_address = proxy::proxy_address(head_address)?;
 
     versioned_tags::link_with_content(
@@ -61,9 +202,7 @@ pub fn update_perspective_head(
         &proxy_address,
         "head".into(),
         head_address.clone(),
-    )?;
-
-    Ok(())
+    )
 }
 
 pub fn get_perspective_head(perspective_address: &Address) -> ZomeApiResult<Option<Address>> {
@@ -71,16 +210,288 @@ pub fn get_perspective_head(perspective_address: &Address) -> ZomeApiResult<Opti
 }
 
 /**
- * Updates the head commit associated with the given perspective
+ * Returns all the heads competing for the latest version of the perspective, the winning one first
+ */
+pub fn get_perspective_heads(perspective_address: Address) -> ZomeApiResult<Vec<Address>> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    versioned_tags::get_last_contents::<Address>(&internal_address, "head".into())
+}
+
+/**
+ * Updates the name of the given perspective, which can then be found by that name
  */
 pub fn update_perspective_name(perspective_address: &Address, name: String) -> ZomeApiResult<()> {
-    let anchor_address = holochain_anchors::anchor("name".into(), name.clone())?;
+    // Remove previous link from name if existent
+    if let Some(previous_name) = get_perspective_name(&perspective_address)? {
+        let previous_name_address = name_anchor_address(&previous_name)?;
+        hdk::remove_link(
+            &previous_name_address,
+            &perspective_address,
+            "name->perspective",
+            "",
+        )?;
+    }
 
+    let anchor_address = name_address(&name)?;
     versioned_tags::link_with_content(&perspective_address, &anchor_address, "name".into(), name)?;
 
+    hdk::link_entries(
+        &anchor_address,
+        &perspective_address,
+        "name->perspective",
+        "",
+    )?;
+
     Ok(())
 }
 
 pub fn get_perspective_name(perspective_address: &Address) -> ZomeApiResult<Option<String>> {
     versioned_tags::get_last_content(&perspective_address, "name".into())
 }
+
+/**
+ * Returns all the perspectives currently named with the given name
+ */
+pub fn get_perspectives_by_name(name: String) -> ZomeApiResult<Vec<Address>> {
+    let address = name_anchor_address(&name)?;
+
+    let links = hdk::get_links(
+        &address,
+        LinkMatch::Exactly("name->perspective"),
+        LinkMatch::Any,
+    )?;
+
+    Ok(links.addresses())
+}
+
+/**
+ * Updates the upstream perspective which the given perspective tracks
+ */
+pub fn update_perspective_upstream(
+    perspective_address: &Address,
+    upstream_address: &Address,
+) -> ZomeApiResult<()> {
+    versioned_tags::link_with_content(
+        &perspective_address,
+        &upstream_address,
+        "upstream".into(),
+        upstream_address.clone(),
+    )?;
+
+    Ok(())
+}
+
+pub fn get_perspective_upstream(perspective_address: &Address) -> ZomeApiResult<Option<Address>> {
+    versioned_tags::get_last_content::<Address>(&perspective_address, "upstream".into())
+}
+
+/**
+ * Returns how many commits the head of the perspective is ahead and behind the head
+ * of its upstream, or None if it doesn't track any upstream
+ */
+pub fn get_upstream_status(perspective_address: Address) -> ZomeApiResult<Option<UpstreamStatus>> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    let upstream = match get_perspective_upstream(&internal_address)? {
+        Some(upstream) => upstream,
+        None => return Ok(None),
+    };
+
+    let head = get_perspective_head(&internal_address)?;
+    let upstream_head = get_perspective_head(&upstream)?;
+
+    let ancestors = history::get_ancestors(head.clone().into_iter().collect())?;
+    let upstream_ancestors = history::get_ancestors(upstream_head.clone().into_iter().collect())?;
+
+    Ok(Some(UpstreamStatus {
+        upstream,
+        head,
+        upstream_head,
+        ahead: ancestors.difference(&upstream_ancestors).count(),
+        behind: upstream_ancestors.difference(&ancestors).count(),
+    }))
+}
+
+/**
+ * Fast-forwards the head of the perspective to the head of its upstream when it descends from it,
+ * otherwise returns both heads and their merge base without updating anything
+ */
+pub fn pull_perspective(perspective_address: Address) -> ZomeApiResult<PullResult> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    let upstream = match get_perspective_upstream(&internal_address)? {
+        Some(upstream) => upstream,
+        None => {
+            return Err(ZomeApiError::from(String::from(
+                "The perspective does not track any upstream",
+            )))
+        }
+    };
+
+    let head = get_perspective_head(&internal_address)?;
+    let upstream_head = get_perspective_head(&upstream)?;
+
+    let new_head = match upstream_head.clone() {
+        Some(new_head) => new_head,
+        None => {
+            return Ok(PullResult {
+                status: PullStatus::UpToDate,
+                head,
+                upstream_head,
+                merge_base: vec![],
+            })
+        }
+    };
+
+    if let Some(current_head) = head.clone() {
+        if history::is_ancestor(new_head.clone(), current_head.clone())? {
+            return Ok(PullResult {
+                status: PullStatus::UpToDate,
+                head,
+                upstream_head,
+                merge_base: vec![],
+            });
+        }
+        if !history::is_ancestor(current_head.clone(), new_head.clone())? {
+            let merge_base = history::find_merge_base(current_head, new_head)?;
+            return Ok(PullResult {
+                status: PullStatus::Diverged,
+                head,
+                upstream_head,
+                merge_base,
+            });
+        }
+    }
+
+    // Fails with a HeadConflict if the head changed while pulling
+    let details = PerspectiveDetails {
+        head: Some(new_head.clone()),
+        ..Default::default()
+    };
+    update_perspective_details(internal_address, details, None, head)?;
+
+    Ok(PullResult {
+        status: PullStatus::FastForwarded,
+        head: Some(new_head),
+        upstream_head,
+        merge_base: vec![],
+    })
+}
+
+/**
+ * Updates whether the given perspective only accepts fast-forward head updates
+ */
+pub fn update_fast_forward_only(
+    perspective_address: &Address,
+    fast_forward_only: bool,
+) -> ZomeApiResult<()> {
+    let value = fast_forward_only.to_string();
+    let anchor_address = holochain_anchors::anchor("fast_forward_only".into(), value.clone())?;
+
+    versioned_tags::link_with_content(
+        &perspective_address,
+        &anchor_address,
+        "fast_forward_only".into(),
+        value,
+    )?;
+
+    Ok(())
+}
+
+pub fn get_fast_forward_only(perspective_address: &Address) -> ZomeApiResult<bool> {
+    Ok(get_fast_forward_only_setting(perspective_address)?.unwrap_or(false))
+}
+
+/**
+ * Returns whether the given perspective only accepts fast-forward head updates,
+ * or None if it was never set
+ */
+pub fn get_fast_forward_only_setting(
+    perspective_address: &Address,
+) -> ZomeApiResult<Option<bool>> {
+    let value: Option<String> =
+        versioned_tags::get_last_content(&perspective_address, "fast_forward_only".into())?;
+
+    Ok(value.map(|v| v == "true"))
+}
+
+/**
+ * Returns the versions of the given detail of the perspective, to audit or undo its changes
+ */
+pub fn get_perspective_detail_history(
+    perspective_address: Address,
+    detail: &str,
+    from_count: Option<usize>,
+    limit: usize,
+) -> ZomeApiResult<Vec<Version>> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    versioned_tags::get_history(&internal_address, detail.into(), from_count, limit)
+}
+
+/**
+ * Fails if the perspective only accepts fast-forward head updates and the given head
+ * does not descend from its current head
+ */
+pub fn check_fast_forward(
+    perspective_address: &Address,
+    head_address: &Address,
+) -> ZomeApiResult<()> {
+    if !get_fast_forward_only(perspective_address)? {
+        return Ok(());
+    }
+
+    match get_perspective_head(perspective_address)? {
+        Some(current_head) => match history::is_ancestor(current_head, head_address.clone())? {
+            true => Ok(()),
+            false => Err(ZomeApiError::from(String::from(
+                "The new head is not a fast-forward of the current head",
+            ))),
+        },
+        None => Ok(()),
+    }
+}
+
+/**
+ * Fails with a HeadConflict error if the current head of the perspective is not the expected one,
+ * None meaning that the perspective has no head
+ */
+pub fn check_expected_head(
+    perspective_address: &Address,
+    expected_head: &Option<Address>,
+) -> ZomeApiResult<()> {
+    match is_current_head(perspective_address, expected_head)? {
+        true => Ok(()),
+        false => Err(ZomeApiError::from(format!(
+            "{}: expected head {:?} but the current head is {:?}",
+            HEAD_CONFLICT_ERROR,
+            expected_head,
+            get_perspective_head(perspective_address)?
+        ))),
+    }
+}
+
+/**
+ * Returns whether the current head of the perspective is the given one, which can be
+ * referenced by its CID or its address in this app
+ */
+pub fn is_current_head(
+    perspective_address: &Address,
+    head: &Option<Address>,
+) -> ZomeApiResult<bool> {
+    match (get_perspective_head(perspective_address)?, head) {
+        (Some(current_head), Some(head)) => proxy::same_entry(&current_head, head),
+        (current_head, head) => Ok(current_head.is_none() && head.is_none()),
+    }
+}
+
+/** Private helpers **/
+
+fn name_address(name: &String) -> ZomeApiResult<Address> {
+    holochain_anchors::anchor("name".into(), name.clone())
+}
+
+fn name_anchor_address(name: &String) -> ZomeApiResult<Address> {
+    utils::anchor_address("name".into(), name.clone())
+}
@@ -1,42 +1,78 @@
+use crate::{canonical, verifiers};
 use hdk::{
     error::ZomeApiResult,
-    holochain_core_types::{
-        entry::Entry,
-        signature::{Provenance, Signature},
-    },
+    holochain_core_types::entry::Entry,
     holochain_json_api::{error::JsonError, json::JsonString},
     holochain_persistence_api::cas::content::Address,
 };
 
+/**
+ * Platform that produced the signature of a proof
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ProofType {
+    #[serde(rename = "ed25519", alias = "ECDSA")]
+    Ed25519,
+    #[serde(rename = "eth-personal-sign")]
+    EthPersonalSign,
+    #[serde(rename = "none")]
+    None,
+}
+
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct Proof {
-    r#type: String,
+    r#type: ProofType,
     signature: String,
 }
 
 impl Proof {
     pub fn from(payload: JsonString) -> ZomeApiResult<Proof> {
-        let payload_str = String::from(payload);
-        let payload_replaced = payload_str.replace("\"", "\\\"");
-        let signature = hdk::sign(payload_replaced)?;
+        let signature = hdk::sign(canonical::to_canonical_json(payload)?)?;
 
         Ok(Proof {
-            r#type: String::from("ECDSA"),
+            r#type: ProofType::Ed25519,
             signature: signature,
         })
     }
 
+    /**
+     * Verifies that the given secured entry carries one valid proof for each of its creators,
+     * using the verifier registered for each proof type
+     */
     pub fn verify<S, T>(secured: T) -> Result<(), String>
     where
         T: Secured<S>,
     {
-        let proof = secured.proof();
-        let provenance = Provenance::new(secured.creator_id(), Signature::from(proof.signature));
+        let creators_ids = secured.creators_ids();
+        let proofs = secured.proofs();
+
+        if creators_ids.len() != proofs.len() {
+            return Err(String::from("Every creator must sign the entry"));
+        }
+
+        for (creator_id, proof) in creators_ids.iter().zip(proofs.iter()) {
+            proof.verify_for(creator_id, secured.payload())?;
+        }
+
+        Ok(())
+    }
 
-        match hdk::verify_signature(provenance, secured.payload())? {
-            true => Ok(()),
-            false => Err(String::from("Failed to verify signature")),
+    /**
+     * Verifies that this proof was signed by the given creator over the given payload,
+     * with the proof type that corresponds to the format of the creator id
+     */
+    pub fn verify_for(&self, creator_id: &Address, payload: JsonString) -> Result<(), String> {
+        let expected_type = verifiers::proof_type_for(creator_id);
+        if self.r#type != expected_type {
+            return Err(format!(
+                "Creator {} must provide a proof of type {:?}, found {:?}",
+                creator_id, expected_type, self.r#type
+            ));
         }
+
+        let canonical_payload = canonical::to_canonical_json(payload)?;
+
+        verifiers::verifier_for(&self.r#type).verify(creator_id, &canonical_payload, &self.signature)
     }
 }
 
@@ -46,7 +82,7 @@ where
 {
     fn from_data(data: S) -> ZomeApiResult<Self>;
     fn entry(&self) -> Entry;
-    fn creator_id(&self) -> Address;
+    fn creators_ids(&self) -> Vec<Address>;
     fn payload(&self) -> JsonString;
-    fn proof(&self) -> Proof;
+    fn proofs(&self) -> Vec<Proof>;
 }
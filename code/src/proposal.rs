@@ -0,0 +1,326 @@
+use crate::perspective_details::{self, PerspectiveDetails, HEAD_CONFLICT_ERROR};
+use crate::permissions::{self, Role};
+use crate::{perspective, utils, versioned_tags};
+use hdk::prelude::*;
+use hdk::AGENT_ADDRESS;
+use std::collections::HashSet;
+
+/**
+ * Proposed update of the head of a perspective, based on the head it had when proposed
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeadUpdate {
+    pub perspectiveId: Address,
+    pub oldHead: Option<Address>,
+    pub newHead: Address,
+}
+
+/**
+ * Request to update the head of the target perspective
+ *
+ * Updates are a list to match the proposals of other _Prtcl providers, but they can only
+ * target the perspective of the proposal, as its children are not tracked by this zome
+ */
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Proposal {
+    pub creatorId: Address,
+    pub timestamp: u128,
+    pub toPerspectiveId: Address,
+    pub fromPerspectiveId: Option<Address>,
+    pub updates: Vec<HeadUpdate>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProposalStatus {
+    Open,
+    Approved,
+    Rejected,
+    Executed,
+}
+
+impl ProposalStatus {
+    fn as_str(&self) -> &str {
+        match self {
+            ProposalStatus::Open => "open",
+            ProposalStatus::Approved => "approved",
+            ProposalStatus::Rejected => "rejected",
+            ProposalStatus::Executed => "executed",
+        }
+    }
+
+    fn parse(status: &str) -> ZomeApiResult<ProposalStatus> {
+        match status {
+            "open" => Ok(ProposalStatus::Open),
+            "approved" => Ok(ProposalStatus::Approved),
+            "rejected" => Ok(ProposalStatus::Rejected),
+            "executed" => Ok(ProposalStatus::Executed),
+            _ => Err(ZomeApiError::from(format!("Unknown proposal status {}", status))),
+        }
+    }
+
+    /**
+     * Open proposals can be approved, rejected or executed, and approved ones rejected or executed
+     */
+    fn can_transition_to(&self, status: &ProposalStatus) -> bool {
+        match (self, status) {
+            (ProposalStatus::Open, ProposalStatus::Open) => false,
+            (ProposalStatus::Open, _) => true,
+            (ProposalStatus::Approved, ProposalStatus::Rejected) => true,
+            (ProposalStatus::Approved, ProposalStatus::Executed) => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn definition() -> ValidatingEntryType {
+    entry!(
+        name: "proposal",
+        description: "a proposal to update the heads of a perspective",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: |validation_data: hdk::EntryValidationData<Proposal>| {
+            match validation_data {
+                EntryValidationData::Create { entry, validation_data } => {
+                    if !validation_data.sources().contains(&entry.creatorId) {
+                        return Err("The author of the proposal must be its creator".into());
+                    }
+                    validate_updates(&entry)
+                },
+                _ => Err("Cannot modify or delete proposals".into())
+            }
+        },
+        links: [
+            from!(
+                "perspective",
+                link_type: "proposal",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, validation_data) = utils::split_link_validation_data(validation_data);
+                    let proposal: Proposal =
+                        hdk::utils::get_as_type(link_data.link().target().clone())?;
+
+                    if link_data.link().base().clone() != proposal.toPerspectiveId {
+                        return Err("The proposal targets another perspective".into());
+                    }
+                    match validation_data.sources().contains(&proposal.creatorId) {
+                        true => Ok(()),
+                        false => Err("Only the creator of the proposal can link it".into()),
+                    }
+                }
+            ),
+            to!(
+                holochain_anchors::ANCHOR_TYPE,
+                link_type: "status",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: |validation_data: hdk::LinkValidationData | {
+                    let (link_data, validation_data) = utils::split_link_validation_data(validation_data);
+                    let proposal: Proposal =
+                        hdk::utils::get_as_type(link_data.link().base().clone())?;
+
+                    permissions::validate_role(
+                        &proposal.toPerspectiveId,
+                        &validation_data,
+                        Role::Admin,
+                    )
+                }
+            )
+        ]
+    )
+}
+
+// Public handlers
+
+/**
+ * Create a proposal to update the heads of the given perspective
+ */
+pub fn create_proposal(
+    to_perspective_address: Address,
+    from_perspective_address: Option<Address>,
+    updates: Vec<HeadUpdate>,
+    description: Option<String>,
+    timestamp: u128,
+) -> ZomeApiResult<Address> {
+    let to_perspective_id = perspective::internal_perspective_address(&to_perspective_address)?;
+
+    // Stored with internal addresses, so that validation can compare them with the target
+    let updates = updates
+        .into_iter()
+        .map(|update| {
+            Ok(HeadUpdate {
+                perspectiveId: perspective::internal_perspective_address(&update.perspectiveId)?,
+                ..update
+            })
+        })
+        .collect::<ZomeApiResult<Vec<HeadUpdate>>>()?;
+
+    let proposal = Proposal {
+        creatorId: AGENT_ADDRESS.clone(),
+        timestamp,
+        toPerspectiveId: to_perspective_id.clone(),
+        fromPerspectiveId: from_perspective_address,
+        updates,
+        description,
+    };
+    let proposal_address = hdk::commit_entry(&Entry::App("proposal".into(), proposal.into()))?;
+
+    hdk::link_entries(&to_perspective_id, &proposal_address, "proposal", "")?;
+
+    Ok(proposal_address)
+}
+
+/**
+ * Returns the addresses of the proposals targeting the given perspective
+ */
+pub fn get_perspective_proposals(perspective_address: Address) -> ZomeApiResult<Vec<Address>> {
+    let internal_address = perspective::internal_perspective_address(&perspective_address)?;
+
+    let links = hdk::get_links(
+        &internal_address,
+        LinkMatch::Exactly("proposal"),
+        LinkMatch::Any,
+    )?;
+
+    Ok(links.addresses())
+}
+
+pub fn get_proposal_status(proposal_address: &Address) -> ZomeApiResult<ProposalStatus> {
+    let status: Option<String> =
+        versioned_tags::get_last_content(&proposal_address, "status".into())?;
+
+    match status {
+        Some(s) => ProposalStatus::parse(&s),
+        None => Ok(ProposalStatus::Open),
+    }
+}
+
+/**
+ * Approve or reject the given proposal
+ */
+pub fn update_proposal_status(
+    proposal_address: Address,
+    status: ProposalStatus,
+) -> ZomeApiResult<()> {
+    if status == ProposalStatus::Executed {
+        return Err(ZomeApiError::from(String::from(
+            "Proposals can only be marked as executed by executing them",
+        )));
+    }
+
+    set_status(&proposal_address, status)
+}
+
+/**
+ * Apply the head updates of the given proposal and mark it as executed
+ *
+ * Everything that can make the execution fail is checked before updating any head: the calling
+ * agent must be an admin of every updated perspective, the heads must not have changed since
+ * the proposal was made, and fast-forward only perspectives must receive a descendant
+ */
+pub fn execute_proposal(proposal_address: Address) -> ZomeApiResult<()> {
+    let proposal: Proposal = hdk::utils::get_as_type(proposal_address.clone())?;
+
+    let current_status = get_proposal_status(&proposal_address)?;
+    if !current_status.can_transition_to(&ProposalStatus::Executed) {
+        return Err(ZomeApiError::from(format!(
+            "Cannot execute a proposal which is {}",
+            current_status.as_str()
+        )));
+    }
+    validate_updates(&proposal).map_err(ZomeApiError::from)?;
+
+    for update in proposal.updates.iter() {
+        match permissions::get_agent_role(&update.perspectiveId, &AGENT_ADDRESS)? {
+            Some(ref role) if *role >= Role::Admin => (),
+            _ => {
+                return Err(ZomeApiError::from(format!(
+                    "Only admins of perspective {} can execute the proposal",
+                    update.perspectiveId
+                )))
+            }
+        }
+
+        if !perspective_details::is_current_head(&update.perspectiveId, &update.oldHead)? {
+            return Err(ZomeApiError::from(format!(
+                "{}: the head of perspective {} has changed since the proposal was made",
+                HEAD_CONFLICT_ERROR, update.perspectiveId
+            )));
+        }
+
+        perspective_details::check_fast_forward(&update.perspectiveId, &update.newHead)?;
+    }
+
+    for update in proposal.updates.into_iter() {
+        // The old head was checked above, so the fast-forward check is not repeated
+        perspective_details::update_perspective_details(
+            update.perspectiveId,
+            PerspectiveDetails {
+                head: Some(update.newHead),
+                ..Default::default()
+            },
+            Some(true),
+            update.oldHead,
+        )?;
+    }
+
+    set_status(&proposal_address, ProposalStatus::Executed)
+}
+
+/** Private helpers **/
+
+/**
+ * Checks that the proposal updates the head of its target perspective, once
+ */
+fn validate_updates(proposal: &Proposal) -> Result<(), String> {
+    if proposal.updates.is_empty() {
+        return Err("Proposals must update at least one head".into());
+    }
+
+    let mut targets = HashSet::new();
+    for update in proposal.updates.iter() {
+        if update.perspectiveId != proposal.toPerspectiveId {
+            return Err(format!(
+                "Proposals can only update the head of their target perspective, found {}",
+                update.perspectiveId
+            ));
+        }
+        if !targets.insert(update.perspectiveId.clone()) {
+            return Err(format!(
+                "Proposals can only update the head of perspective {} once",
+                update.perspectiveId
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn set_status(proposal_address: &Address, status: ProposalStatus) -> ZomeApiResult<()> {
+    let current_status = get_proposal_status(&proposal_address)?;
+    if !current_status.can_transition_to(&status) {
+        return Err(ZomeApiError::from(format!(
+            "Cannot change the status of the proposal from {} to {}",
+            current_status.as_str(),
+            status.as_str()
+        )));
+    }
+
+    let anchor_address =
+        holochain_anchors::anchor("proposal_status".into(), status.as_str().into())?;
+
+    versioned_tags::link_with_content(
+        &proposal_address,
+        &anchor_address,
+        "status".into(),
+        status.as_str(),
+    )?;
+
+    Ok(())
+}
@@ -1,15 +1,21 @@
+use crate::{canonical, utils};
 use cid::{Cid, Codec, Version};
 use hdk::prelude::*;
 use holochain_anchors;
 use multibase;
 use multihash::Sha2_256;
-use serde_cbor::to_vec;
-use serde_json;
 
 pub fn proxy_address(proxied_address: &Address) -> ZomeApiResult<Address> {
     holochain_anchors::anchor("proxy".into(), proxied_address.to_string())
 }
 
+/**
+ * Returns the address of the proxy anchor of the given address without creating it
+ */
+pub fn proxy_anchor_address(proxied_address: &Address) -> ZomeApiResult<Address> {
+    utils::anchor_address("proxy".into(), proxied_address.to_string())
+}
+
 pub fn set_entry_proxy(entry: &Entry, proxied_address: &Option<Address>) -> ZomeApiResult<Address> {
     let entry_address = entry.address();
 
@@ -36,21 +42,11 @@ pub fn set_entry_proxy(entry: &Entry, proxied_address: &Option<Address>) -> Zome
     Ok(final_proxy_address)
 }
 
-fn get_raw_multihash(content: JsonString) -> ZomeApiResult<Address> {
-    let hashable: serde_json::Value = match serde_json::from_str(content.to_string().as_str()) {
-        Ok(value) => Ok(value),
-        Err(e) => Err(ZomeApiError::from(format!(
-            "Error deserializing json: {:?}",
-            e
-        ))),
-    }?;
-
-    let vec = match to_vec(&hashable) {
-        Ok(v) => Ok(v),
-        Err(_) => Err(ZomeApiError::from(String::from(
-            "Could not serialize content",
-        ))),
-    }?;
+/**
+ * Returns the CID of the given content, over its canonical DAG-CBOR encoding
+ */
+pub fn get_raw_multihash(content: JsonString) -> ZomeApiResult<Address> {
+    let vec = canonical::to_canonical_cbor(content)?;
 
     let mh = Sha2_256::digest(&vec);
     let cid = Cid::new(Version::V1, Codec::DagCBOR, mh).unwrap();
@@ -64,7 +60,7 @@ pub fn internal_address(maybe_proxy_address: &Address) -> ZomeApiResult<Option<A
     match hdk::get_entry(&maybe_proxy_address)? {
         Some(_) => Ok(Some(maybe_proxy_address.clone())),
         None => {
-            let anchor_address = proxy_address(&maybe_proxy_address)?;
+            let anchor_address = proxy_anchor_address(&maybe_proxy_address)?;
 
             let links = hdk::get_links(
                 &anchor_address,
@@ -86,3 +82,17 @@ pub fn internal_address(maybe_proxy_address: &Address) -> ZomeApiResult<Option<A
         }
     }
 }
+
+/**
+ * Returns whether both addresses point to the same entry, through its address or a proxy
+ */
+pub fn same_entry(address_a: &Address, address_b: &Address) -> ZomeApiResult<bool> {
+    if address_a == address_b {
+        return Ok(true);
+    }
+
+    match (internal_address(address_a)?, internal_address(address_b)?) {
+        (Some(internal_a), Some(internal_b)) => Ok(internal_a == internal_b),
+        _ => Ok(false),
+    }
+}
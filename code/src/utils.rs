@@ -1,7 +1,12 @@
 use crate::{proof::Secured, proxy};
-use hdk::DNA_ADDRESS;
+use hdk::{LinkValidationData, DNA_ADDRESS};
 use hdk::{
-    error::ZomeApiResult, holochain_core_types::link::LinkMatch,
+    error::ZomeApiResult,
+    holochain_core_types::{
+        entry::Entry,
+        link::{link_data::LinkData, LinkMatch},
+        validation::ValidationData,
+    },
     holochain_persistence_api::cas::content::Address,
 };
 use holochain_wasm_utils::api_serialization::get_links::GetLinksResult;
@@ -34,6 +39,48 @@ pub fn get_cas_id() -> String {
     String::from("holochain://") + &String::from(DNA_ADDRESS.to_owned())
 }
 
+/**
+ * Returns the address of the given anchor without creating it, so that it can be used
+ * when reading and validating, where no entries can be written
+ */
+pub fn anchor_address(anchor_type: String, anchor_text: String) -> ZomeApiResult<Address> {
+    let anchor = holochain_anchors::Anchor {
+        anchor_type,
+        anchor_text: Some(anchor_text),
+    };
+
+    hdk::entry_address(&Entry::App(
+        holochain_anchors::ANCHOR_TYPE.into(),
+        anchor.into(),
+    ))
+}
+
+/**
+ * Returns whether hard links can point to addresses that cannot be resolved in this app,
+ * as set by the "allow_dangling_references" property of the DNA
+ */
+pub fn allow_dangling_references() -> bool {
+    match hdk::property("allow_dangling_references") {
+        Ok(value) => String::from(value) == "true",
+        Err(_) => false,
+    }
+}
+
+/**
+ * Returns the type of the app entry the given address resolves to, if any
+ */
+pub fn resolve_entry_type(address: &Address) -> ZomeApiResult<Option<String>> {
+    let internal_address = match proxy::internal_address(address)? {
+        Some(a) => a,
+        None => return Ok(None),
+    };
+
+    match hdk::get_entry(&internal_address)? {
+        Some(Entry::App(entry_type, _)) => Ok(Some(entry_type.to_string())),
+        _ => Ok(None),
+    }
+}
+
 pub fn remove_previous_links(
     base_address: &Address,
     link_option: Option<String>,
@@ -80,6 +127,31 @@ pub fn get_links(
     hdk::get_links(base_address, link_type, tag)
 }
 
+/**
+ * Returns the link and the validation data of either a link addition or removal
+ */
+pub fn split_link_validation_data(
+    validation_data: LinkValidationData,
+) -> (LinkData, ValidationData) {
+    match validation_data {
+        LinkValidationData::LinkAdd {
+            link,
+            validation_data,
+        } => (link, validation_data),
+        LinkValidationData::LinkRemove {
+            link,
+            validation_data,
+        } => (link, validation_data),
+    }
+}
+
+/**
+ * Appends the given key to the JSON Pointer (RFC 6901) path, escaping it
+ */
+pub fn pointer_path(path: &String, key: &str) -> String {
+    format!("{}/{}", path, key.replace("~", "~0").replace("/", "~1"))
+}
+
 fn option_to_string(link_option: Option<String>) -> String {
     match link_option {
         Some(link) => link,
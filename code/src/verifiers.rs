@@ -0,0 +1,162 @@
+use crate::proof::ProofType;
+use hcid::HcidEncoding;
+use hdk::{
+    holochain_core_types::signature::{Provenance, Signature},
+    holochain_persistence_api::cas::content::Address,
+};
+use secp256k1::{recover, Message, RecoveryId};
+use tiny_keccak::{Hasher, Keccak};
+
+/**
+ * Verifies signatures produced by a given platform
+ */
+pub trait ProofVerifier {
+    fn verify(&self, creator_id: &Address, payload: &String, signature: &String)
+        -> Result<(), String>;
+}
+
+/**
+ * Returns the verifier for the given proof type
+ */
+pub fn verifier_for(proof_type: &ProofType) -> Box<dyn ProofVerifier> {
+    match proof_type {
+        ProofType::Ed25519 => Box::new(HolochainVerifier),
+        ProofType::EthPersonalSign => Box::new(EthereumVerifier),
+        ProofType::None => Box::new(UnsignedVerifier),
+    }
+}
+
+/**
+ * Returns the only proof type accepted from creators with the given id,
+ * which is None for creators of platforms without a verifier
+ */
+pub fn proof_type_for(creator_id: &Address) -> ProofType {
+    let id = creator_id.to_string();
+
+    if is_ethereum_address(&id) {
+        ProofType::EthPersonalSign
+    } else if is_agent_address(&id) {
+        ProofType::Ed25519
+    } else {
+        ProofType::None
+    }
+}
+
+/**
+ * Ed25519 signatures produced by hdk::sign, where the creator is the holochain agent address
+ */
+pub struct HolochainVerifier;
+
+impl ProofVerifier for HolochainVerifier {
+    fn verify(
+        &self,
+        creator_id: &Address,
+        payload: &String,
+        signature: &String,
+    ) -> Result<(), String> {
+        let provenance = Provenance::new(creator_id.clone(), Signature::from(signature.clone()));
+
+        match hdk::verify_signature(provenance, payload.clone())? {
+            true => Ok(()),
+            false => Err(String::from("Failed to verify signature")),
+        }
+    }
+}
+
+/**
+ * Hex encoded secp256k1 signatures produced by Ethereum's personal_sign,
+ * where the creator is the checksummed or lowercase ethereum address of the signer
+ */
+pub struct EthereumVerifier;
+
+impl ProofVerifier for EthereumVerifier {
+    fn verify(
+        &self,
+        creator_id: &Address,
+        payload: &String,
+        signature: &String,
+    ) -> Result<(), String> {
+        let bytes = hex::decode(signature.trim_start_matches("0x"))
+            .map_err(|_| String::from("Signature is not hex encoded"))?;
+
+        if bytes.len() != 65 {
+            return Err(String::from("Signature must be 65 bytes long"));
+        }
+
+        // personal_sign returns v as 27 or 28, while the recovery id is 0 or 1
+        let v = bytes[64];
+        let recovery_id = RecoveryId::parse(if v >= 27 { v - 27 } else { v })
+            .map_err(|_| String::from("Invalid signature recovery id"))?;
+
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes.copy_from_slice(&bytes[..64]);
+
+        let message = Message::parse(&personal_message_hash(payload));
+        let public_key = recover(
+            &message,
+            &secp256k1::Signature::parse(&signature_bytes),
+            &recovery_id,
+        )
+        .map_err(|_| String::from("Could not recover the signer from the signature"))?;
+
+        // The address is the last 20 bytes of the hash of the uncompressed public key
+        let public_key_hash = keccak256(&public_key.serialize()[1..]);
+        let signer_address = format!("0x{}", hex::encode(&public_key_hash[12..]));
+
+        match signer_address == creator_id.to_string().to_lowercase() {
+            true => Ok(()),
+            false => Err(String::from("Failed to verify signature")),
+        }
+    }
+}
+
+/**
+ * Entries coming from platforms without signatures, which make no claim of authorship
+ */
+pub struct UnsignedVerifier;
+
+impl ProofVerifier for UnsignedVerifier {
+    fn verify(
+        &self,
+        _creator_id: &Address,
+        _payload: &String,
+        signature: &String,
+    ) -> Result<(), String> {
+        match signature.is_empty() {
+            true => Ok(()),
+            false => Err(String::from("Unsigned proofs cannot contain a signature")),
+        }
+    }
+}
+
+/** Private helpers **/
+
+/**
+ * Hex encoded 20 byte address, in lowercase or checksummed form
+ */
+fn is_ethereum_address(id: &String) -> bool {
+    id.len() == 42 && id.starts_with("0x") && id[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/**
+ * HcS address of a holochain agent, which must decode with a valid checksum
+ */
+fn is_agent_address(id: &String) -> bool {
+    match HcidEncoding::with_kind("hcs0") {
+        Ok(codec) => codec.decode(id).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn personal_message_hash(payload: &String) -> [u8; 32] {
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", payload.len(), payload);
+    keccak256(prefixed.as_bytes())
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(bytes);
+    hasher.finalize(&mut output);
+    output
+}
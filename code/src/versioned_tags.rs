@@ -1,13 +1,35 @@
-use hdk::holochain_wasm_utils::api_serialization::get_links::LinksResult;
+use hdk::holochain_wasm_utils::api_serialization::get_links::{GetLinksOptions, LinksResult};
 use hdk::prelude::*;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct VersionedTag {
+    content: String,
+    count: usize,
+}
+
+/**
+ * A version of the content of a versioned link
+ */
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Version {
+    pub content: String,
+    pub count: usize,
+    pub author: Option<Address>,
+    pub timestamp: Option<String>,
+}
+
+/**
+ * Links the target from the base as the next version of the given link type,
+ * returning the count of the new version
+ */
 pub fn link_with_content<T>(
     base: &Address,
     target: &Address,
     link_type: String,
     tag_content: T,
-) -> ZomeApiResult<Address>
+) -> ZomeApiResult<usize>
 where
     T: Into<String>,
 {
@@ -15,25 +37,51 @@ where
         &base,
         LinkMatch::Exactly(link_type.as_str()),
         LinkMatch::Any,
-    )?;
+    )?
+    .count;
 
-    let tag = serialize_tag::<T>(tag_content, count.count);
+    let tag = serialize_tag::<T>(tag_content, count)?;
+    hdk::link_entries(&base, &target, link_type, tag)?;
 
-    hdk::link_entries(&base, &target, link_type, tag)
+    Ok(count)
 }
 
+/**
+ * Returns the link with the highest count, breaking ties between concurrent links
+ * deterministically so that every node agrees on the same one
+ */
 pub fn get_last_link(base: &Address, link_type: String) -> ZomeApiResult<Option<LinksResult>> {
-    let links_result = hdk::get_links(
+    let links = get_last_links(&base, link_type)?;
+
+    Ok(links.get(0).map(|l| l.clone()))
+}
+
+/**
+ * Returns all the links with the highest count, which are more than one when different agents
+ * created them concurrently
+ *
+ * The links are sorted with the latest created first, and by author address if created
+ * at the same time, so the first link is the one that wins
+ */
+pub fn get_last_links(base: &Address, link_type: String) -> ZomeApiResult<Vec<LinksResult>> {
+    let links_result = hdk::get_links_with_options(
         &base,
         LinkMatch::Exactly(link_type.as_str()),
         LinkMatch::Any,
+        GetLinksOptions {
+            headers: true,
+            ..Default::default()
+        },
     )?;
 
     let mut links = links_result.links();
 
-    links.sort_by(|t1, t2| count_from_tag(t2.tag.clone()).cmp(&count_from_tag(t1.tag.clone())));
+    let max_count = links.iter().map(|l| count_from_tag(l.tag.clone())).max();
+    links.retain(|l| Some(count_from_tag(l.tag.clone())) == max_count);
 
-    Ok(links.get(0).map(|l| l.clone()))
+    links.sort_by(compare_concurrent_links);
+
+    Ok(links)
 }
 
 pub fn get_last_content<T>(base: &Address, link_type: String) -> ZomeApiResult<Option<T>>
@@ -48,25 +96,120 @@ where
     }
 }
 
+/**
+ * Returns the contents of all the links with the highest count, the winning one first
+ */
+pub fn get_last_contents<T>(base: &Address, link_type: String) -> ZomeApiResult<Vec<T>>
+where
+    T: TryFrom<String>,
+{
+    get_last_links(&base, link_type)?
+        .into_iter()
+        .map(|link| deserialize_tag::<T>(link.tag).map(|t| t.0))
+        .collect()
+}
+
+/**
+ * Returns the versions of the given link type in ascending count, starting from the given count
+ *
+ * Concurrent versions with the same count are all returned, the winning one first
+ */
+pub fn get_history(
+    base: &Address,
+    link_type: String,
+    from_count: Option<usize>,
+    limit: usize,
+) -> ZomeApiResult<Vec<Version>> {
+    let links_result = hdk::get_links_with_options(
+        &base,
+        LinkMatch::Exactly(link_type.as_str()),
+        LinkMatch::Any,
+        GetLinksOptions {
+            headers: true,
+            ..Default::default()
+        },
+    )?;
+
+    let mut links = links_result.links();
+    links.sort_by(|l1, l2| {
+        count_from_tag(l1.tag.clone())
+            .cmp(&count_from_tag(l2.tag.clone()))
+            .then_with(|| compare_concurrent_links(l1, l2))
+    });
+
+    links
+        .into_iter()
+        .filter(|l| count_from_tag(l.tag.clone()) >= from_count.unwrap_or(0))
+        .take(limit)
+        .map(|link| {
+            let (content, count) = deserialize_tag::<String>(link.tag.clone())?;
+
+            Ok(Version {
+                content,
+                count,
+                author: link_author(&link).map(Address::from),
+                timestamp: link_timestamp(&link),
+            })
+        })
+        .collect()
+}
+
 /** Private helpers **/
 
-fn serialize_tag<T>(tag_content: T, count: usize) -> String
+fn compare_concurrent_links(l1: &LinksResult, l2: &LinksResult) -> Ordering {
+    link_timestamp(l2)
+        .cmp(&link_timestamp(l1))
+        .then_with(|| link_author(l1).cmp(&link_author(l2)))
+}
+
+fn link_timestamp(link: &LinksResult) -> Option<String> {
+    link.headers.get(0).map(|h| h.timestamp().to_string())
+}
+
+fn link_author(link: &LinksResult) -> Option<String> {
+    link.headers
+        .get(0)
+        .and_then(|h| h.provenances().get(0).map(|p| p.source().to_string()))
+}
+
+/**
+ * Parses both tags encoded as json and legacy tags encoded as "content:{},count:{}"
+ */
+fn parse_tag(tag: &str) -> Option<VersionedTag> {
+    if let Ok(versioned_tag) = serde_json::from_str::<VersionedTag>(tag) {
+        return Some(versioned_tag);
+    }
+
+    // Legacy tags did not escape their content, so the count is after the last separator
+    if !tag.starts_with("content:") {
+        return None;
+    }
+    let legacy = &tag["content:".len()..];
+    let separator = legacy.rfind(",count:")?;
+
+    Some(VersionedTag {
+        content: String::from(&legacy[..separator]),
+        count: legacy[separator + ",count:".len()..].parse::<usize>().ok()?,
+    })
+}
+
+fn serialize_tag<T>(tag_content: T, count: usize) -> ZomeApiResult<String>
 where
     T: Into<String>,
 {
-    let content: String = tag_content.into();
-    format!("content:{},count:{}", content, count)
+    let tag = VersionedTag {
+        content: tag_content.into(),
+        count,
+    };
+
+    serde_json::to_string(&tag)
+        .map_err(|e| ZomeApiError::from(format!("Could not serialize tag: {:?}", e)))
 }
 
 fn count_from_tag(tag: String) -> usize {
-    let split: Vec<&str> = tag.split(",").collect();
-    let count_string = split[1];
-
-    let count: &str = count_string.split(":").collect::<Vec<&str>>()[1];
-
-    match count.parse::<usize>() {
-        Ok(c) => c,
-        Err(_) => 0,
+    match parse_tag(&tag) {
+        Some(versioned_tag) => versioned_tag.count,
+        None => 0,
     }
 }
 
@@ -74,17 +217,98 @@ fn deserialize_tag<T>(tag: String) -> ZomeApiResult<(T, usize)>
 where
     T: TryFrom<String>,
 {
-    let split: Vec<&str> = tag.split(",").collect();
-    let content_string = split[0];
-    let count_string = split[1];
-
-    let content: &str = content_string.split(":").collect::<Vec<&str>>()[1];
-    let count: &str = count_string.split(":").collect::<Vec<&str>>()[1];
+    let versioned_tag = parse_tag(&tag)
+        .ok_or(ZomeApiError::from(String::from("Could not deserialize tag")))?;
 
-    match T::try_from(String::from(content)) {
-        Ok(result) => Ok((result, String::from(count).parse::<usize>().unwrap())),
+    match T::try_from(versioned_tag.content) {
+        Ok(result) => Ok((result, versioned_tag.count)),
         Err(_) => Err(ZomeApiError::from(String::from(
             "Could not deserialize tag",
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRICKY_CONTENTS: [&str; 9] = [
+        "Notes: draft, v2",
+        "content:evil,count:99",
+        "{\"content\":\"nested\",\"count\":3}",
+        "quotes \" and \\ backslashes",
+        "línea\nnueva\ty tabulador",
+        "日本語のノート",
+        "emoji 🌱🍄, with: separators",
+        ",",
+        ":",
+    ];
+
+    fn versioned_tag(content: &str, count: usize) -> Option<VersionedTag> {
+        Some(VersionedTag {
+            content: String::from(content),
+            count,
+        })
+    }
+
+    // xorshift64*, enough to generate reproducible arbitrary strings without extra dependencies
+    fn random_string(state: &mut u64) -> String {
+        let mut next = || {
+            *state ^= *state >> 12;
+            *state ^= *state << 25;
+            *state ^= *state >> 27;
+            state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        };
+
+        let length = next() % 40;
+        (0..length)
+            .filter_map(|_| std::char::from_u32((next() % 0x11_0000) as u32))
+            .collect()
+    }
+
+    #[test]
+    fn parses_json_tags() {
+        for content in TRICKY_CONTENTS.iter() {
+            let tag = serialize_tag(*content, 7).unwrap();
+            assert_eq!(parse_tag(&tag), versioned_tag(content, 7));
+        }
+    }
+
+    #[test]
+    fn parses_legacy_tags() {
+        for content in TRICKY_CONTENTS.iter() {
+            let tag = format!("content:{},count:7", content);
+            assert_eq!(parse_tag(&tag), versioned_tag(content, 7));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_tags() {
+        for tag in [
+            "",
+            "count:7",
+            "content:no count",
+            "content:bad count,count:seven",
+            "{\"content\":\"missing count\"}",
+        ]
+        .iter()
+        {
+            assert_eq!(parse_tag(tag), None, "{:?}", tag);
+        }
+    }
+
+    #[test]
+    fn parses_arbitrary_unicode_contents() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+
+        for count in 0..1000 {
+            let content = random_string(&mut state);
+
+            let tag = serialize_tag(content.clone(), count).unwrap();
+            assert_eq!(parse_tag(&tag), versioned_tag(&content, count), "{:?}", tag);
+
+            let legacy_tag = format!("content:{},count:{}", content, count);
+            assert_eq!(parse_tag(&legacy_tag), versioned_tag(&content, count));
+        }
+    }
+}
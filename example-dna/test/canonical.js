@@ -0,0 +1,62 @@
+const {
+  createData,
+  getCanonicalJson,
+  canonicalJson,
+} = require("./utils");
+
+// Cross-check vectors shared with other _Prtcl providers and clients
+const vectors = require("./vectors/canonical.json");
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "canonical json matches the cross-check vectors",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      for (const vector of vectors) {
+        const result = await getCanonicalJson(vector.payload)(alice);
+        t.equal(result.Ok, vector.canonicalJson, vector.description);
+
+        // The helper used by clients must agree with the zome
+        t.equal(
+          canonicalJson(vector.payload),
+          vector.canonicalJson,
+          vector.description
+        );
+      }
+    }
+  );
+
+  orchestrator.registerScenario(
+    "signed payloads reject numbers which are not safe integers",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      for (const payload of [
+        { value: 1.5 },
+        { value: [1e21] },
+        { value: { nested: 9007199254740992 } },
+      ]) {
+        const result = await getCanonicalJson(payload)(alice);
+        t.ok(result.Err, JSON.stringify(payload));
+        t.throws(() => canonicalJson(payload));
+
+        // Data entries are not signed and can contain any number
+        const data = await createData(payload)(alice);
+        t.ok(data.Ok, JSON.stringify(payload));
+      }
+    }
+  );
+
+  orchestrator.registerScenario(
+    "data cids are computed over canonical dag-cbor",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      for (const vector of vectors) {
+        const result = await createData(vector.payload)(alice);
+        t.equal(result.Ok, vector.cid, vector.description);
+      }
+    }
+  );
+};
@@ -0,0 +1,63 @@
+const {
+  createPerspective,
+  createData,
+  createCommit,
+  parseResponse,
+} = require("./utils");
+
+const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
+
+// Run against a dna with the allow_dangling_references property off
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "hard links must resolve when dangling references are not allowed",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      // Data which has not reached the DHT yet is found in the source chain of alice
+      const dataId = parseResponse(await createData({ text: "hello" })(alice));
+      const commitAddress = await createCommit(dataId, [], "valid commit")(
+        alice
+      );
+      t.ok(commitAddress);
+      await s.consistency();
+
+      // Bob can build on top of commits of other agents
+      const bobCommit = await createCommit(dataId, [commitAddress], "child")(
+        bob
+      );
+      t.ok(bobCommit);
+
+      // Data that cannot be resolved is rejected
+      let result = await alice.call("uprtcl", "uprtcl", "create_commit", {
+        dataId: SAMPLE_ADDRESS1,
+        parentsIds: [],
+        message: "dangling data",
+        timestamp: Date.now(),
+      });
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // So are parents that cannot be resolved...
+      result = await alice.call("uprtcl", "uprtcl", "create_commit", {
+        dataId,
+        parentsIds: [SAMPLE_ADDRESS1],
+        message: "dangling parent",
+        timestamp: Date.now(),
+      });
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // ... or that are not commits
+      const perspectiveAddress = await createPerspective()(alice);
+      result = await alice.call("uprtcl", "uprtcl", "create_commit", {
+        dataId,
+        parentsIds: [perspectiveAddress],
+        message: "wrong parent",
+        timestamp: Date.now(),
+      });
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+    }
+  );
+};
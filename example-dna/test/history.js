@@ -0,0 +1,153 @@
+const {
+  createCommit,
+  createNewPerspectiveAndCommit,
+  updatePerspectiveDetails,
+  getPerspectiveDetails,
+  getCommitHistory,
+  findMergeBase,
+} = require("./utils");
+
+const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario("page through a commit history", async (s, t) => {
+    const { alice } = await s.players({ alice: config }, true);
+
+    // root <- left, right <- merge
+    const root = await createCommit(SAMPLE_ADDRESS1, [], "root")(alice);
+    const left = await createCommit(SAMPLE_ADDRESS1, [root], "left")(alice);
+    const right = await createCommit(SAMPLE_ADDRESS1, [root], "right")(alice);
+    const merge = await createCommit(
+      SAMPLE_ADDRESS1,
+      [left, right],
+      "merge"
+    )(alice);
+    await s.consistency();
+
+    const firstPage = await getCommitHistory(merge, 2)(alice);
+    t.deepEqual(
+      firstPage.commits.map((c) => c.id),
+      [merge, left]
+    );
+    t.ok(firstPage.next_cursor);
+
+    // The root is only returned once even if both branches reach it
+    const secondPage = await getCommitHistory(
+      merge,
+      2,
+      firstPage.next_cursor
+    )(alice);
+    t.deepEqual(
+      secondPage.commits.map((c) => c.id),
+      [right, root]
+    );
+    t.equal(secondPage.next_cursor, null);
+    t.equal(secondPage.commits[1].commit.payload.message, "root");
+
+    // Pages that end with the last commit have no next cursor
+    const wholeHistory = await getCommitHistory(merge, 4)(alice);
+    t.equal(wholeHistory.commits.length, 4);
+    t.equal(wholeHistory.next_cursor, null);
+
+    // Pages must have at least one commit
+    const result = await alice.call("uprtcl", "uprtcl", "get_commit_history", {
+      commit_id: merge,
+      limit: 0,
+      cursor: null,
+    });
+    t.ok(result.Err);
+  });
+
+  orchestrator.registerScenario(
+    "find the merge bases of criss-cross merges",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const root = await createCommit(SAMPLE_ADDRESS1, [], "root")(alice);
+      const a1 = await createCommit(SAMPLE_ADDRESS1, [root], "a1")(alice);
+      const b1 = await createCommit(SAMPLE_ADDRESS1, [root], "b1")(alice);
+      const a2 = await createCommit(SAMPLE_ADDRESS1, [a1, b1], "a2")(alice);
+      const b2 = await createCommit(SAMPLE_ADDRESS1, [b1, a1], "b2")(alice);
+      await s.consistency();
+
+      // An ancestor is the merge base of its descendants
+      t.deepEqual(await findMergeBase(a1, root)(alice), [root]);
+      // Diverging branches meet at their fork point
+      t.deepEqual(await findMergeBase(a1, b1)(alice), [root]);
+
+      // Both a1 and b1 are best common ancestors of a2 and b2
+      const bases = await findMergeBase(a2, b2)(alice);
+      t.deepEqual(bases.sort(), [a1, b1].sort());
+    }
+  );
+
+  orchestrator.registerScenario(
+    "fast-forward only perspectives reject unrelated heads",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit("root", SAMPLE_ADDRESS1, "master")(
+        alice
+      );
+      await s.consistency();
+
+      // Never set until it's updated
+      let details = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(details.fast_forward_only, null);
+
+      let result = await updatePerspectiveDetails(perspectiveAddress, {
+        fast_forward_only: true,
+      })(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      details = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(details.fast_forward_only, true);
+
+      const child = await createCommit(SAMPLE_ADDRESS1, [commitAddress], "child")(
+        alice
+      );
+      const unrelated = await createCommit(SAMPLE_ADDRESS1, [], "unrelated")(
+        alice
+      );
+      await s.consistency();
+
+      t.equal(
+        (
+          await alice.call("uprtcl", "uprtcl", "is_ancestor", {
+            ancestor: commitAddress,
+            descendant: child,
+          })
+        ).Ok,
+        true
+      );
+
+      // Descendants of the current head are accepted
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: child,
+      })(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      // Unrelated commits are rejected unless forced
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: unrelated,
+      })(alice);
+      t.ok(result.Err);
+
+      result = await updatePerspectiveDetails(
+        perspectiveAddress,
+        { head: unrelated },
+        true
+      )(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, unrelated);
+    }
+  );
+};
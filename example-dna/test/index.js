@@ -1,4 +1,5 @@
 
+const fs = require("fs");
 const path = require("path");
 
 const {
@@ -28,6 +29,26 @@ const config = Config.gen(
   }
 );
 
+// Same dna but rejecting hard links that cannot be resolved in the app
+const strictDnaPath = path.join(__dirname, "../dist/hc-uprtcl-strict.dna.json");
+const strictDnaJson = JSON.parse(fs.readFileSync(dnaPath));
+strictDnaJson.properties = {
+  ...strictDnaJson.properties,
+  allow_dangling_references: false,
+};
+fs.writeFileSync(strictDnaPath, JSON.stringify(strictDnaJson));
+
+const strictDna = Config.dna(strictDnaPath, "scaffold-test-strict");
+const strictConfig = Config.gen(
+  { uprtcl: strictDna },
+  {
+    network: {
+      type: "sim2h",
+      sim2h_url: "ws://localhost:9000",
+    },
+  }
+);
+
 const orchestrator = new Orchestrator({
   waiter: {
     softTimeout: 20000,
@@ -40,6 +61,14 @@ const orchestrator = new Orchestrator({
 //require('./discovery')(orchestrator, config);
 //require('./workspace')(diorama.registerScenario);
 require('./uprtcl')(orchestrator, config);
+require('./canonical')(orchestrator, config);
+require('./history')(orchestrator, config);
+require('./versioned_tags')(orchestrator, config);
+require('./permissions')(orchestrator, config);
+require('./proposals')(orchestrator, config);
+require('./upstream')(orchestrator, config);
+require('./merge')(orchestrator, config);
+require('./hard_links')(orchestrator, strictConfig);
 //require('./draft')(diorama.registerScenario);
 
 orchestrator.run();
@@ -0,0 +1,267 @@
+const {
+  getEntry,
+  createNewPerspectiveAndCommit,
+  getPerspectiveDetails,
+  createData,
+  commitData,
+  diffCommits,
+  mergeCommits,
+  commitPatch,
+  cherryPick,
+  revertCommit,
+  getCommitHistory,
+} = require("./utils");
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "merge changes to different keys",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const base = await commitData(
+        { title: "Title", body: { text: "Body", links: [] } },
+        [],
+        "base"
+      )(alice);
+      const ours = await commitData(
+        { title: "New title", body: { text: "Body", links: [] } },
+        [base],
+        "ours"
+      )(alice);
+      const theirs = await commitData(
+        { title: "Title", body: { text: "Body", links: ["a/b"] }, tags: [] },
+        [base],
+        "theirs"
+      )(alice);
+      await s.consistency();
+
+      const result = await mergeCommits(ours, theirs, "merge")(alice);
+      t.deepEqual(result.conflicts, []);
+      await s.consistency();
+
+      const merge = await getEntry(result.commit)(alice);
+      t.deepEqual(merge.payload.parentsIds, [ours, theirs]);
+      t.equal(merge.payload.message, "merge");
+
+      const data = await getEntry(merge.payload.dataId)(alice);
+      t.deepEqual(data, {
+        title: "New title",
+        body: { text: "Body", links: ["a/b"] },
+        tags: [],
+      });
+    }
+  );
+
+  orchestrator.registerScenario(
+    "report conflicting paths without merging",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const base = await commitData(
+        { title: "Title", "a/b": { text: "Body" } },
+        [],
+        "base"
+      )(alice);
+      const ours = await commitData(
+        { title: "Ours", "a/b": { text: "Our body" } },
+        [base],
+        "ours"
+      )(alice);
+      const theirs = await commitData(
+        { title: "Title", "a/b": { text: "Their body" } },
+        [base],
+        "theirs"
+      )(alice);
+      await s.consistency();
+
+      const result = await mergeCommits(ours, theirs, "merge")(alice);
+      t.equal(result.commit, null);
+      t.deepEqual(result.conflicts, [
+        {
+          path: "/a~1b/text",
+          base: "Body",
+          ours: "Our body",
+          theirs: "Their body",
+        },
+      ]);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "commits with more than one merge base conflict as a whole",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const root = await commitData({ title: "Root" }, [], "root")(alice);
+      const a1 = await commitData({ title: "A" }, [root], "a1")(alice);
+      const b1 = await commitData({ title: "B" }, [root], "b1")(alice);
+      const a2 = await commitData({ title: "A2" }, [a1, b1], "a2")(alice);
+      const b2 = await commitData({ title: "B2" }, [b1, a1], "b2")(alice);
+      await s.consistency();
+
+      const result = await mergeCommits(a2, b2, "merge")(alice);
+      t.equal(result.commit, null);
+      t.deepEqual(result.conflicts, [
+        {
+          path: "",
+          base: null,
+          ours: { title: "A2" },
+          theirs: { title: "B2" },
+        },
+      ]);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "diff the data of two commits as a json patch",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const from = await commitData(
+        { title: "Title", "a/b": 1, links: ["x", "y", "z"], old: true },
+        [],
+        "from"
+      )(alice);
+      const to = await commitData(
+        { title: "New title", "a/b": 1, links: ["x", "w"], tags: ["new"] },
+        [from],
+        "to"
+      )(alice);
+      await s.consistency();
+
+      const patch = await diffCommits(from, to)(alice);
+      t.deepEqual(patch, [
+        { op: "replace", path: "/links/1", value: "w" },
+        { op: "remove", path: "/links/2" },
+        { op: "remove", path: "/old" },
+        { op: "replace", path: "/title", value: "New title" },
+        { op: "add", path: "/tags", value: ["new"] },
+      ]);
+
+      t.deepEqual(await diffCommits(from, from)(alice), []);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "commit a json patch on top of the head of a perspective",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const { Ok: dataId } = await createData({ title: "Title", links: [] })(
+        alice
+      );
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit("base", dataId, "master")(alice);
+      await s.consistency();
+
+      const { Ok: newCommit } = await commitPatch(
+        perspectiveAddress,
+        commitAddress,
+        [
+          { op: "replace", path: "/title", value: "New title" },
+          { op: "add", path: "/links/-", value: "a" },
+        ],
+        "edit"
+      )(alice);
+      await s.consistency();
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, newCommit);
+
+      const commit = await getEntry(newCommit)(alice);
+      t.deepEqual(commit.payload.parentsIds, [commitAddress]);
+      t.deepEqual(await getEntry(commit.payload.dataId)(alice), {
+        title: "New title",
+        links: ["a"],
+      });
+
+      // Patches based on an outdated head or that don't apply are rejected
+      let result = await commitPatch(perspectiveAddress, commitAddress, [
+        { op: "remove", path: "/links" },
+      ])(alice);
+      t.ok(JSON.stringify(result.Err).includes("HeadConflict"));
+
+      result = await commitPatch(perspectiveAddress, newCommit, [
+        { op: "remove", path: "/missing" },
+      ])(alice);
+      t.ok(result.Err);
+
+      // The base commit can be given by its address in the app instead of its CID
+      const {
+        commits: [{ address: newCommitAddress }],
+      } = await getCommitHistory(newCommit, 1)(alice);
+      t.notEqual(newCommitAddress, newCommit);
+
+      result = await commitPatch(perspectiveAddress, newCommitAddress, [
+        { op: "add", path: "/links/-", value: "b" },
+      ])(alice);
+      t.ok(result.Ok);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "cherry-pick and revert the changes of a commit",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const { Ok: dataId } = await createData({ title: "Title", body: "" })(
+        alice
+      );
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit("base", dataId, "master")(alice);
+
+      // A commit on another branch which only changes the body
+      const picked = await commitData(
+        { title: "Title", body: "Body" },
+        [commitAddress],
+        "Write the body\n\nWith some details"
+      )(alice);
+      await s.consistency();
+
+      const { Ok: retitled } = await commitPatch(
+        perspectiveAddress,
+        commitAddress,
+        [{ op: "replace", path: "/title", value: "New title" }],
+        "retitle"
+      )(alice);
+      await s.consistency();
+
+      const pick = await cherryPick(perspectiveAddress, picked)(alice);
+      t.deepEqual(pick.conflicts, []);
+      await s.consistency();
+
+      let commit = await getEntry(pick.commit)(alice);
+      t.deepEqual(commit.payload.parentsIds, [retitled]);
+      t.equal(
+        commit.payload.message,
+        `Write the body\n\nWith some details\n\nCherry-picked-from: ${picked}`
+      );
+      t.deepEqual(await getEntry(commit.payload.dataId)(alice), {
+        title: "New title",
+        body: "Body",
+      });
+
+      const revert = await revertCommit(perspectiveAddress, picked)(alice);
+      t.deepEqual(revert.conflicts, []);
+      await s.consistency();
+
+      commit = await getEntry(revert.commit)(alice);
+      t.deepEqual(commit.payload.parentsIds, [pick.commit]);
+      t.equal(
+        commit.payload.message,
+        `Revert "Write the body"\n\nReverts: ${picked}`
+      );
+      t.deepEqual(await getEntry(commit.payload.dataId)(alice), {
+        title: "New title",
+        body: "",
+      });
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, revert.commit);
+    }
+  );
+};
@@ -0,0 +1,104 @@
+const {
+  createCommit,
+  createNewPerspectiveAndCommit,
+  getPerspectiveDetails,
+  updatePerspectiveDetails,
+  inviteCollaborator,
+  getMyInvitations,
+  acceptInvite,
+  revokeCollaborator,
+  getPermissions,
+} = require("./utils");
+
+const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "invite a collaborator to write on a perspective",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "bob's commit"
+      )(bob);
+
+      const invitationAddress = await inviteCollaborator(
+        perspectiveAddress,
+        bobAddress,
+        "writer"
+      )(alice);
+      await s.consistency();
+
+      // Bob cannot write until he accepts the invitation
+      let result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: bobCommit,
+      })(bob);
+      t.ok(result.Err);
+
+      const invitations = await getMyInvitations()(bob);
+      t.deepEqual(invitations, [invitationAddress]);
+
+      result = await acceptInvite(invitationAddress)(bob);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      const permissions = await getPermissions(perspectiveAddress)(alice);
+      t.equal(permissions.owner, aliceAddress);
+      t.deepEqual(permissions.collaborators, [
+        { agent_id: bobAddress, role: "writer" },
+      ]);
+
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: bobCommit,
+      })(bob);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, bobCommit);
+
+      // Writers cannot change the settings of the perspective
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        fast_forward_only: true,
+      })(bob);
+      t.ok(result.Err);
+
+      // Writers cannot invite other agents
+      result = await bob.call("uprtcl", "uprtcl", "invite_collaborator", {
+        perspective_address: perspectiveAddress,
+        agent_address: aliceAddress,
+        role: "reader",
+        timestamp: Date.now(),
+      });
+      t.ok(result.Err);
+
+      // Once revoked, bob cannot write nor accept the invitation again
+      result = await revokeCollaborator(perspectiveAddress, bobAddress)(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        name: "bob's master",
+      })(bob);
+      t.ok(result.Err);
+
+      result = await acceptInvite(invitationAddress)(bob);
+      t.ok(result.Err);
+    }
+  );
+};
@@ -0,0 +1,205 @@
+const {
+  createCommit,
+  createNewPerspectiveAndCommit,
+  getPerspectiveDetails,
+  updatePerspectiveDetails,
+  inviteCollaborator,
+  acceptInvite,
+  createProposal,
+  getPerspectiveProposals,
+  getProposalStatus,
+  updateProposalStatus,
+  executeProposal,
+} = require("./utils");
+
+const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "propose an update to a perspective of another agent",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "bob's commit"
+      )(bob);
+      await s.consistency();
+
+      const proposalAddress = await createProposal(
+        perspectiveAddress,
+        [
+          {
+            perspectiveId: perspectiveAddress,
+            oldHead: commitAddress,
+            newHead: bobCommit,
+          },
+        ],
+        "Update master"
+      )(bob);
+
+      // Writers can update the head themselves, but only admins can execute proposals
+      const invitationAddress = await inviteCollaborator(
+        perspectiveAddress,
+        bobAddress,
+        "writer"
+      )(alice);
+      await s.consistency();
+      await acceptInvite(invitationAddress)(bob);
+      await s.consistency();
+
+      let result = await executeProposal(proposalAddress)(bob);
+      t.ok(result.Err);
+
+      let { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, commitAddress);
+
+      const proposals = await getPerspectiveProposals(perspectiveAddress)(
+        alice
+      );
+      t.deepEqual(proposals, [proposalAddress]);
+
+      let status = await getProposalStatus(proposalAddress)(alice);
+      t.equal(status, "open");
+
+      result = await executeProposal(proposalAddress)(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      ({ head } = await getPerspectiveDetails(perspectiveAddress)(alice));
+      t.equal(head, bobCommit);
+
+      status = await getProposalStatus(proposalAddress)(bob);
+      t.equal(status, "executed");
+
+      // Executed proposals cannot be rejected afterwards
+      result = await updateProposalStatus(proposalAddress, "rejected")(alice);
+      t.ok(result.Err);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "proposals can only update their target perspective",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const master = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const develop = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "develop"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [master.commitAddress],
+        "bob's commit"
+      )(bob);
+      await s.consistency();
+
+      // A proposal to master cannot update develop
+      let result = await bob.call("uprtcl", "uprtcl", "create_proposal", {
+        to_perspective_address: master.perspectiveAddress,
+        from_perspective_address: null,
+        updates: [
+          {
+            perspectiveId: develop.perspectiveAddress,
+            oldHead: develop.commitAddress,
+            newHead: bobCommit,
+          },
+        ],
+        description: null,
+        timestamp: Date.now(),
+      });
+      t.ok(result.Err);
+
+      // Nor update master twice
+      const update = {
+        perspectiveId: master.perspectiveAddress,
+        oldHead: master.commitAddress,
+        newHead: bobCommit,
+      };
+      result = await bob.call("uprtcl", "uprtcl", "create_proposal", {
+        to_perspective_address: master.perspectiveAddress,
+        from_perspective_address: null,
+        updates: [update, { ...update, newHead: master.commitAddress }],
+        description: null,
+        timestamp: Date.now(),
+      });
+      t.ok(result.Err);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "proposals based on an outdated head cannot be executed",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "bob's commit"
+      )(bob);
+      const aliceCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "alice's commit"
+      )(alice);
+      await s.consistency();
+
+      const proposalAddress = await createProposal(perspectiveAddress, [
+        {
+          perspectiveId: perspectiveAddress,
+          oldHead: commitAddress,
+          newHead: bobCommit,
+        },
+      ])(bob);
+
+      await updatePerspectiveDetails(perspectiveAddress, {
+        head: aliceCommit,
+      })(alice);
+      await s.consistency();
+
+      const result = await executeProposal(proposalAddress)(alice);
+      t.ok(result.Err);
+      t.ok(JSON.stringify(result.Err).includes("HeadConflict"));
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, aliceCommit);
+
+      // The proposal stays open
+      const status = await getProposalStatus(proposalAddress)(alice);
+      t.equal(status, "open");
+    }
+  );
+};
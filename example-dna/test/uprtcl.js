@@ -2,21 +2,42 @@
 const {
   getEntry,
   createPerspective,
+  clonePerspective,
   getContextPerspectives,
+  getMyPerspectives,
+  getAgentPerspectives,
+  getPerspectivesByName,
   getPerspectiveDetails,
   updatePerspectiveDetails,
+  commitToPerspective,
+  getPerspectiveHeads,
+  getPerspectiveHeadHistory,
+  getPerspectiveNameHistory,
+  getPerspectiveContextHistory,
+  inviteCollaborator,
+  acceptInvite,
+  getPermissions,
   createCommit,
+  cloneCommit,
+  draftCommit,
+  signCommit,
+  getLatestDraft,
+  getMyDrafts,
   createCommitInPerspective,
   createNewPerspectiveAndCommit,
   buildPerspective,
   buildCommit,
   buildProvenance,
   parseEntryResult,
+  parseResponse,
 } = require("./utils");
 
 const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
 const SAMPLE_ADDRESS2 = "QmePeufDdo28ZcPnXhMJqCEEPPwDqq5yeqnCErQfd37UgE";
 
+// Address of the ethereum account with private key 0x00..01
+const ETH_ADDRESS = "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+
 module.exports = (orchestrator, config) => {
   orchestrator.registerScenario(
     "create perspective with proxy addresses",
@@ -182,6 +203,689 @@ module.exports = (orchestrator, config) => {
       t.equal(perspectiveHead3, secondCommitAddress);
     }
   );
+  orchestrator.registerScenario(
+    "clone a commit with a forged signature fails",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const commitAddress = await createCommit(
+        SAMPLE_ADDRESS1,
+        [],
+        "original message"
+      )(alice);
+      await s.consistency();
+
+      const commit = await getEntry(commitAddress)(alice);
+
+      // Cloning the untouched commit keeps alice's authorship
+      let result = await cloneCommit(commit)(bob);
+      t.equal(result.Ok, commitAddress);
+
+      // Tampering with the payload invalidates alice's signature
+      commit.payload.message = "forged message";
+      result = await cloneCommit(commit)(bob);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+    }
+  );
+  orchestrator.registerScenario(
+    "clone commits created on other platforms",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const payload = {
+        creatorsIds: [ETH_ADDRESS],
+        dataId: SAMPLE_ADDRESS1,
+        message: "cloned from ethereum",
+        parentsIds: [],
+        timestamp: 1590000000000,
+      };
+
+      // personal_sign of the canonical json of the payload
+      let result = await cloneCommit({
+        payload,
+        proof: {
+          type: "eth-personal-sign",
+          signature:
+            "0x66cff5c1808e18105bebfbfa4e68a0c7a27b0038db52bc7c1eddf34a83aa83ce11da967a442487329e0ca126d460f6112fdc828e5adfb3eb7e5a171ea87e742e1c",
+        },
+      })(alice);
+      t.equal(Object.keys(result).includes("Ok"), true);
+
+      // The same signature does not hold for another payload
+      result = await cloneCommit({
+        payload: { ...payload, message: "forged message" },
+        proof: {
+          type: "eth-personal-sign",
+          signature:
+            "0x66cff5c1808e18105bebfbfa4e68a0c7a27b0038db52bc7c1eddf34a83aa83ce11da967a442487329e0ca126d460f6112fdc828e5adfb3eb7e5a171ea87e742e1c",
+        },
+      })(alice);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // Ethereum creators must sign their commits...
+      result = await cloneCommit({
+        payload: { ...payload, message: "unsigned" },
+        proof: { type: "none", signature: "" },
+      })(alice);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // ... with their own proof type
+      result = await cloneCommit({
+        payload,
+        proof: {
+          type: "ed25519",
+          signature:
+            "0x66cff5c1808e18105bebfbfa4e68a0c7a27b0038db52bc7c1eddf34a83aa83ce11da967a442487329e0ca126d460f6112fdc828e5adfb3eb7e5a171ea87e742e1c",
+        },
+      })(alice);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // Unsigned commits are only accepted from creators of platforms without a verifier...
+      result = await cloneCommit({
+        payload: {
+          ...payload,
+          creatorsIds: ["did:web:example.org"],
+          message: "unsigned",
+        },
+        proof: { type: "none", signature: "" },
+      })(alice);
+      t.equal(Object.keys(result).includes("Ok"), true);
+
+      // ... and not from holochain agents, which can always sign
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+      result = await cloneCommit({
+        payload: { ...payload, creatorsIds: [aliceAddress] },
+        proof: { type: "none", signature: "" },
+      })(alice);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+    }
+  );
+  orchestrator.registerScenario(
+    "the first agent to clone a perspective from another platform owns it",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+
+      const perspective = {
+        payload: {
+          authority: "https://example.org",
+          creatorId: "did:web:example.org",
+          timestamp: 1590000000000,
+        },
+        proof: { type: "none", signature: "" },
+      };
+      const { Ok: perspectiveAddress } = await clonePerspective(perspective)(
+        alice
+      );
+      t.ok(perspectiveAddress);
+      await s.consistency();
+
+      // Cloning it again does not transfer its custody
+      await clonePerspective(perspective)(bob);
+      await s.consistency();
+
+      const permissions = await getPermissions(perspectiveAddress)(bob);
+      t.equal(permissions.owner, "did:web:example.org");
+      t.equal(permissions.custodian, aliceAddress);
+
+      const commitAddress = await createCommit(
+        SAMPLE_ADDRESS1,
+        [],
+        "first"
+      )(alice);
+      let result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: commitAddress,
+        name: "mirror",
+      })(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS2,
+        [commitAddress],
+        "bob"
+      )(bob);
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: bobCommit,
+      })(bob);
+      t.ok(result.Err);
+
+      // The custodian can invite other agents to write on it
+      const invitationAddress = await inviteCollaborator(
+        perspectiveAddress,
+        bobAddress,
+        "writer"
+      )(alice);
+      await s.consistency();
+      await acceptInvite(invitationAddress)(bob);
+      await s.consistency();
+
+      result = await updatePerspectiveDetails(perspectiveAddress, {
+        head: bobCommit,
+      })(bob);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      const { head, name } = await getPerspectiveDetails(perspectiveAddress)(
+        alice
+      );
+      t.equal(head, bobCommit);
+      t.equal(name, "mirror");
+    }
+  );
+  orchestrator.registerScenario(
+    "create a commit signed by two creators",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+
+      // Alice drafts the commit and signs it
+      const draft = await draftCommit(SAMPLE_ADDRESS1, [
+        aliceAddress,
+        bobAddress,
+      ])(alice);
+      t.deepEqual(draft.missing_creators, [bobAddress]);
+      t.equal(draft.commit_address, null);
+      await s.consistency();
+
+      // Only listed creators can sign
+      const { carol } = await s.players({ carol: config }, true);
+      const error = await carol.call("uprtcl", "uprtcl", "sign_commit", {
+        draft_address: draft.draft_address,
+      });
+      t.ok(error.Err);
+
+      // Bob signs and the commit gets created
+      const signed = await signCommit(draft.draft_address)(bob);
+      t.deepEqual(signed.missing_creators, []);
+      t.ok(signed.commit_address);
+      await s.consistency();
+
+      const commit = await getEntry(signed.commit_address)(alice);
+      t.deepEqual(commit.payload.creatorsIds, [aliceAddress, bobAddress]);
+      t.equal(commit.coauthorsProofs.length, 1);
+
+      // A commit missing the signature of a creator is rejected
+      delete commit.coauthorsProofs;
+      const result = await cloneCommit(commit)(alice);
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+    }
+  );
+  orchestrator.registerScenario(
+    "drafts gather the signatures of all their versions",
+    async (s, t) => {
+      const { alice, bob, carol } = await s.players(
+        { alice: config, bob: config, carol: config },
+        true
+      );
+
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+      const carolAddress = carol.instance("uprtcl").agentAddress;
+
+      // Drafts need at least one creator, and none of them can be repeated
+      let result = await alice.call("uprtcl", "uprtcl", "draft_commit", {
+        dataId: SAMPLE_ADDRESS1,
+        parentsIds: [],
+        message: "",
+        timestamp: Date.now(),
+        creatorsIds: [],
+      });
+      t.ok(result.Err);
+      result = await alice.call("uprtcl", "uprtcl", "draft_commit", {
+        dataId: SAMPLE_ADDRESS1,
+        parentsIds: [],
+        message: "",
+        timestamp: Date.now(),
+        creatorsIds: [aliceAddress, bobAddress, aliceAddress],
+      });
+      t.ok(result.Err);
+
+      const draft = await draftCommit(SAMPLE_ADDRESS1, [
+        aliceAddress,
+        bobAddress,
+        carolAddress,
+      ])(alice);
+      await s.consistency();
+
+      // Every creator can find the draft
+      t.deepEqual(await getMyDrafts()(bob), [draft.draft_address]);
+      t.deepEqual(await getMyDrafts()(carol), [draft.draft_address]);
+
+      // Signing creates a new version, which is found from the original draft
+      const bobSigned = await signCommit(draft.draft_address)(bob);
+      t.equal(bobSigned.draft_address, draft.draft_address);
+      t.deepEqual(bobSigned.missing_creators, [carolAddress]);
+      await s.consistency();
+
+      let latest = await getLatestDraft(draft.draft_address)(alice);
+      t.deepEqual(latest.missing_creators, [carolAddress]);
+      t.equal(latest.commit_address, null);
+
+      // Carol signs the original draft, and bob's signature is kept
+      const carolSigned = await signCommit(draft.draft_address)(carol);
+      t.deepEqual(carolSigned.missing_creators, []);
+      t.ok(carolSigned.commit_address);
+      await s.consistency();
+
+      latest = await getLatestDraft(draft.draft_address)(bob);
+      t.deepEqual(latest.missing_creators, []);
+      t.equal(latest.commit_address, carolSigned.commit_address);
+
+      const commit = await getEntry(carolSigned.commit_address)(alice);
+      t.deepEqual(commit.payload.creatorsIds, [
+        aliceAddress,
+        bobAddress,
+        carolAddress,
+      ]);
+      t.equal(commit.coauthorsProofs.length, 2);
+    }
+  );
+  orchestrator.registerScenario(
+    "commit parents must resolve to commits",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const perspectiveAddress = await createPerspective()(alice);
+      await s.consistency();
+
+      // A perspective is not a valid parent
+      const result = await alice.call("uprtcl", "uprtcl", "create_commit", {
+        dataId: SAMPLE_ADDRESS1,
+        parentsIds: [perspectiveAddress],
+        message: "wrong parent",
+        timestamp: Date.now(),
+      });
+      t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+
+      // External addresses are allowed by the example dna
+      const commitAddress = await createCommit(
+        SAMPLE_ADDRESS1,
+        [SAMPLE_ADDRESS2],
+        "external parent"
+      )(alice);
+      t.ok(commitAddress);
+    }
+  );
+  orchestrator.registerScenario(
+    "head updates with an outdated expected head conflict",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      await s.consistency();
+
+      const aliceCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "alice"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS2,
+        [commitAddress],
+        "bob"
+      )(bob);
+
+      // Alice moves the head first
+      let result = await updatePerspectiveDetails(
+        perspectiveAddress,
+        { head: aliceCommit },
+        false,
+        commitAddress
+      )(alice);
+      t.ok(result.Ok === null);
+      await s.consistency();
+
+      // Bob's update was based on the previous head
+      result = await updatePerspectiveDetails(
+        perspectiveAddress,
+        { head: bobCommit },
+        false,
+        commitAddress
+      )(bob);
+      t.equal(JSON.stringify(result.Err).includes("HeadConflict"), true);
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(bob);
+      t.equal(head, aliceCommit);
+    }
+  );
+  orchestrator.registerScenario(
+    "concurrent head updates are all kept and resolved the same way",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+      const bobAddress = bob.instance("uprtcl").agentAddress;
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const invitationAddress = await inviteCollaborator(
+        perspectiveAddress,
+        bobAddress,
+        "writer"
+      )(alice);
+      await s.consistency();
+      await acceptInvite(invitationAddress)(bob);
+
+      const aliceCommit = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "alice"
+      )(alice);
+      const bobCommit = await createCommit(
+        SAMPLE_ADDRESS2,
+        [commitAddress],
+        "bob"
+      )(bob);
+      await s.consistency();
+
+      // Both agents update the head without seeing each other's update
+      const results = await Promise.all([
+        updatePerspectiveDetails(perspectiveAddress, { head: aliceCommit })(
+          alice
+        ),
+        updatePerspectiveDetails(perspectiveAddress, { head: bobCommit })(bob),
+      ]);
+      t.ok(results.every((result) => result.Ok === null));
+      await s.consistency();
+
+      const heads = await getPerspectiveHeads(perspectiveAddress)(alice);
+      t.equal(heads.length, 2);
+      t.deepEqual([...heads].sort(), [aliceCommit, bobCommit].sort());
+      t.deepEqual(await getPerspectiveHeads(perspectiveAddress)(bob), heads);
+
+      // Both agents resolve the conflict to the same winner
+      const aliceDetails = await getPerspectiveDetails(perspectiveAddress)(alice);
+      const bobDetails = await getPerspectiveDetails(perspectiveAddress)(bob);
+      t.equal(aliceDetails.head, heads[0]);
+      t.equal(bobDetails.head, heads[0]);
+    }
+  );
+  orchestrator.registerScenario(
+    "read the history of a perspective head",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      await s.consistency();
+
+      const secondCommit = await createCommitInPerspective(
+        perspectiveAddress,
+        "second commit",
+        SAMPLE_ADDRESS2
+      )(alice);
+      await s.consistency();
+
+      const history = await getPerspectiveHeadHistory(perspectiveAddress)(
+        alice
+      );
+      t.deepEqual(
+        history.map((v) => v.content),
+        [commitAddress, secondCommit]
+      );
+      t.deepEqual(
+        history.map((v) => v.count),
+        [0, 1]
+      );
+      t.equal(history[1].author, aliceAddress);
+      t.ok(history[1].timestamp);
+
+      // Page from the second version
+      const page = await getPerspectiveHeadHistory(perspectiveAddress, 1)(
+        alice
+      );
+      t.deepEqual(
+        page.map((v) => v.content),
+        [secondCommit]
+      );
+    }
+  );
+  orchestrator.registerScenario(
+    "read the history of the name and context of a perspective",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const {
+        perspectiveAddress,
+        context,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      await s.consistency();
+
+      await updatePerspectiveDetails(perspectiveAddress, {
+        name: "develop",
+        context: "new context",
+      })(alice);
+      await s.consistency();
+
+      const names = await getPerspectiveNameHistory(perspectiveAddress)(alice);
+      t.deepEqual(
+        names.map((v) => [v.content, v.count]),
+        [
+          ["master", 0],
+          ["develop", 1],
+        ]
+      );
+
+      const contexts = await getPerspectiveContextHistory(perspectiveAddress)(
+        alice
+      );
+      t.deepEqual(
+        contexts.map((v) => [v.content, v.count]),
+        [
+          [context, 0],
+          ["new context", 1],
+        ]
+      );
+
+      // Page with only the latest version
+      const page = await getPerspectiveNameHistory(perspectiveAddress, 1, 1)(
+        alice
+      );
+      t.deepEqual(
+        page.map((v) => v.content),
+        ["develop"]
+      );
+    }
+  );
+  orchestrator.registerScenario(
+    "only the creator can update the details of a perspective",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const { perspectiveAddress } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      await s.consistency();
+
+      const bobCommit = await createCommit(SAMPLE_ADDRESS2, [], "bob")(bob);
+
+      for (const details of [
+        { head: bobCommit },
+        { name: "hijacked" },
+        { context: "hijacked" },
+      ]) {
+        const result = await updatePerspectiveDetails(
+          perspectiveAddress,
+          details
+        )(bob);
+        t.equal(JSON.stringify(result.Err).includes("ValidationFailed"), true);
+      }
+
+      const { name } = await getPerspectiveDetails(perspectiveAddress)(bob);
+      t.equal(name, "master");
+    }
+  );
+  orchestrator.registerScenario(
+    "list the perspectives created by an agent",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+      const aliceAddress = alice.instance("uprtcl").agentAddress;
+
+      const {
+        perspectiveAddress,
+        context,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const otherPerspective = await createPerspective()(alice);
+      await s.consistency();
+
+      const myPerspectives = await getMyPerspectives()(alice);
+      t.equal(myPerspectives.length, 2);
+      t.ok(myPerspectives.includes(perspectiveAddress));
+      t.ok(myPerspectives.includes(otherPerspective));
+
+      const inContext = await getAgentPerspectives(aliceAddress, context)(bob);
+      t.deepEqual(inContext, [perspectiveAddress]);
+
+      t.deepEqual(await getMyPerspectives()(bob), []);
+
+      // Clones are listed in the perspectives of their creator, not of the cloning agent
+      const perspective = await getEntry(otherPerspective)(alice);
+      const result = await clonePerspective(perspective)(bob);
+      t.equal(result.Ok, otherPerspective);
+      await s.consistency();
+
+      t.ok(
+        (await getAgentPerspectives(aliceAddress)(bob)).includes(
+          otherPerspective
+        )
+      );
+      t.deepEqual(await getMyPerspectives()(bob), []);
+
+      // Perspectives from other platforms are not listed for any agent
+      const { Ok: externalPerspective } = await clonePerspective({
+        payload: {
+          authority: "https://example.org",
+          creatorId: "did:web:example.org",
+          timestamp: 1590000000000,
+        },
+        proof: { type: "none", signature: "" },
+      })(bob);
+      t.ok(externalPerspective);
+      await s.consistency();
+      t.deepEqual(await getMyPerspectives()(bob), []);
+    }
+  );
+  orchestrator.registerScenario(
+    "find perspectives by their current name",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const name = `master-${Math.random()}`;
+      const { perspectiveAddress } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        name
+      )(alice);
+      await s.consistency();
+
+      t.deepEqual(await getPerspectivesByName(name)(alice), [
+        perspectiveAddress,
+      ]);
+
+      await updatePerspectiveDetails(perspectiveAddress, {
+        name: `${name}-renamed`,
+      })(alice);
+      await s.consistency();
+
+      t.deepEqual(await getPerspectivesByName(name)(alice), []);
+      t.deepEqual(
+        await getPerspectivesByName(`${name}-renamed`)(alice),
+        [perspectiveAddress]
+      );
+    }
+  );
+  orchestrator.registerScenario(
+    "save data to a perspective in one call",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const perspectiveAddress = await createPerspective()(alice);
+      await s.consistency();
+
+      const first = parseResponse(
+        await commitToPerspective(perspectiveAddress, { text: "first" })(alice)
+      );
+      t.equal(first.head_version, 0);
+      await s.consistency();
+
+      const second = parseResponse(
+        await commitToPerspective(perspectiveAddress, { text: "second" })(alice)
+      );
+      t.equal(second.head_version, 1);
+      await s.consistency();
+
+      const { head } = await getPerspectiveDetails(perspectiveAddress)(alice);
+      t.equal(head, second.commit_id);
+
+      const commit = await getEntry(second.commit_id)(alice);
+      t.deepEqual(commit.payload.parentsIds, [first.commit_id]);
+      t.equal(commit.payload.dataId, second.data_id);
+      t.deepEqual(await getEntry(second.data_id)(alice), { text: "second" });
+
+      // Agents without write permission cannot save
+      const result = await commitToPerspective(perspectiveAddress, {
+        text: "bob",
+      })(bob);
+      t.ok(result.Err);
+    }
+  );
   /* 
 scenario('create with invalid provenance fails', async (s, t, { alice }) => {
   // create context
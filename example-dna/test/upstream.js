@@ -0,0 +1,184 @@
+const {
+  createCommit,
+  createNewPerspectiveAndCommit,
+  forkPerspective,
+  getPerspectiveDetails,
+  updatePerspectiveDetails,
+  getUpstreamStatus,
+  pullPerspective,
+} = require("./utils");
+
+const SAMPLE_ADDRESS1 = "QmXA9hq87xLVqs4EgrzVZ5hRmaaiYUxpUB9J77GeQ5A2en";
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "fork a perspective of another agent",
+    async (s, t) => {
+      const { alice, bob } = await s.players(
+        { alice: config, bob: config },
+        true
+      );
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+        context,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      await s.consistency();
+
+      const forkAddress = await forkPerspective(
+        perspectiveAddress,
+        "bob's master"
+      )(bob);
+      await s.consistency();
+
+      const details = await getPerspectiveDetails(forkAddress)(bob);
+      t.equal(details.name, "bob's master");
+      t.equal(details.head, commitAddress);
+      t.equal(details.context, context);
+      t.equal(details.upstream, perspectiveAddress);
+
+      // The context of the source can be replaced in the fork
+      const forkInContext = await forkPerspective(
+        perspectiveAddress,
+        "bob's draft",
+        "another context"
+      )(bob);
+      await s.consistency();
+
+      t.equal(
+        (await getPerspectiveDetails(forkInContext)(bob)).context,
+        "another context"
+      );
+    }
+  );
+
+  orchestrator.registerScenario(
+    "count the commits ahead and behind the upstream",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const forkAddress = await forkPerspective(
+        perspectiveAddress,
+        "develop"
+      )(alice);
+      await s.consistency();
+
+      let status = await getUpstreamStatus(forkAddress)(alice);
+      t.equal(status.upstream, perspectiveAddress);
+      t.equal(status.ahead, 0);
+      t.equal(status.behind, 0);
+
+      // master <- upstream1 <- upstream2 in master, master <- local in develop
+      const upstream1 = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "upstream 1"
+      )(alice);
+      const upstream2 = await createCommit(
+        SAMPLE_ADDRESS1,
+        [upstream1],
+        "upstream 2"
+      )(alice);
+      const local = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "local"
+      )(alice);
+      await updatePerspectiveDetails(perspectiveAddress, { head: upstream2 })(
+        alice
+      );
+      await updatePerspectiveDetails(forkAddress, { head: local })(alice);
+      await s.consistency();
+
+      status = await getUpstreamStatus(forkAddress)(alice);
+      t.equal(status.head, local);
+      t.equal(status.upstream_head, upstream2);
+      t.equal(status.ahead, 1);
+      t.equal(status.behind, 2);
+
+      // Perspectives without upstream have no status
+      const result = await alice.call("uprtcl", "uprtcl", "get_upstream_status", {
+        perspective_address: perspectiveAddress,
+      });
+      t.equal(result.Ok, null);
+    }
+  );
+
+  orchestrator.registerScenario(
+    "pull the changes of the upstream",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const {
+        perspectiveAddress,
+        commitAddress,
+      } = await createNewPerspectiveAndCommit(
+        "Commit message",
+        SAMPLE_ADDRESS1,
+        "master"
+      )(alice);
+      const forkAddress = await forkPerspective(
+        perspectiveAddress,
+        "develop"
+      )(alice);
+      await s.consistency();
+
+      let result = await pullPerspective(forkAddress)(alice);
+      t.equal(result.status, "up_to_date");
+
+      const upstream1 = await createCommit(
+        SAMPLE_ADDRESS1,
+        [commitAddress],
+        "upstream 1"
+      )(alice);
+      await updatePerspectiveDetails(perspectiveAddress, { head: upstream1 })(
+        alice
+      );
+      await s.consistency();
+
+      result = await pullPerspective(forkAddress)(alice);
+      t.equal(result.status, "fast_forwarded");
+      t.equal(result.head, upstream1);
+      await s.consistency();
+
+      const { head } = await getPerspectiveDetails(forkAddress)(alice);
+      t.equal(head, upstream1);
+
+      // Both perspectives move on, so the client has to merge them
+      const upstream2 = await createCommit(
+        SAMPLE_ADDRESS1,
+        [upstream1],
+        "upstream 2"
+      )(alice);
+      const local = await createCommit(
+        SAMPLE_ADDRESS1,
+        [upstream1],
+        "local"
+      )(alice);
+      await updatePerspectiveDetails(perspectiveAddress, { head: upstream2 })(
+        alice
+      );
+      await updatePerspectiveDetails(forkAddress, { head: local })(alice);
+      await s.consistency();
+
+      result = await pullPerspective(forkAddress)(alice);
+      t.equal(result.status, "diverged");
+      t.equal(result.head, local);
+      t.equal(result.upstream_head, upstream2);
+      t.deepEqual(result.merge_base, [upstream1]);
+    }
+  );
+};
@@ -24,6 +24,49 @@ const createPerspective = function (timestamp = Date.now()) {
     );
 };
 
+const forkPerspective = function (
+  sourceAddress,
+  name,
+  context = null,
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "fork_perspective", {
+        source_address: sourceAddress,
+        name,
+        context,
+        timestamp,
+      })
+    );
+};
+
+const clonePerspective = function (perspective, previousAddress = null) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "clone_perspective", {
+      previous_address: previousAddress,
+      perspective,
+    });
+};
+
+const getUpstreamStatus = function (perspectiveAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_upstream_status", {
+        perspective_address: perspectiveAddress,
+      })
+    );
+};
+
+const pullPerspective = function (perspectiveAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "pull_perspective", {
+        perspective_address: perspectiveAddress,
+      })
+    );
+};
+
 const getContextPerspectives = function (context) {
   return async (caller) => {
     const perspectives = await caller.call(
@@ -38,6 +81,34 @@ const getContextPerspectives = function (context) {
   };
 };
 
+const getMyPerspectives = function (context = null) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_my_perspectives", {
+        context,
+      })
+    );
+};
+
+const getAgentPerspectives = function (agentAddress, context = null) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_agent_perspectives", {
+        agent_address: agentAddress,
+        context,
+      })
+    );
+};
+
+const getPerspectivesByName = function (name) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_perspectives_by_name", {
+        name,
+      })
+    );
+};
+
 const getPerspectiveDetails = function (perspectiveAddress) {
   return async (caller) => {
     const head = await caller.call(
@@ -52,11 +123,82 @@ const getPerspectiveDetails = function (perspectiveAddress) {
   };
 };
 
-const updatePerspectiveDetails = function (perspectiveAddress, details) {
+const updatePerspectiveDetails = function (
+  perspectiveAddress,
+  details,
+  force = false,
+  expectedHead = null
+) {
   return async (caller) =>
     await caller.call("uprtcl", "uprtcl", "update_perspective_details", {
       perspective_address: perspectiveAddress,
       details,
+      force,
+      expected_head: expectedHead,
+    });
+};
+
+const commitToPerspective = function (
+  perspectiveAddress,
+  data,
+  message = "",
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "commit_to_perspective", {
+      perspective_address: perspectiveAddress,
+      data: JSON.stringify(data),
+      message,
+      timestamp,
+    });
+};
+
+const getPerspectiveHeads = function (perspectiveAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_perspective_heads", {
+        perspective_address: perspectiveAddress,
+      })
+    );
+};
+
+const getPerspectiveDetailHistory = function (zomeFunction) {
+  return function (perspectiveAddress, fromCount = 0, limit = 10) {
+    return async (caller) =>
+      parseResponse(
+        await caller.call("uprtcl", "uprtcl", zomeFunction, {
+          perspective_address: perspectiveAddress,
+          from_count: fromCount,
+          limit,
+        })
+      );
+  };
+};
+
+const getPerspectiveHeadHistory = getPerspectiveDetailHistory(
+  "get_perspective_head_history"
+);
+const getPerspectiveNameHistory = getPerspectiveDetailHistory(
+  "get_perspective_name_history"
+);
+const getPerspectiveContextHistory = getPerspectiveDetailHistory(
+  "get_perspective_context_history"
+);
+
+/** Data */
+
+const getCanonicalJson = function (payload) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "get_canonical_json", {
+      payload: JSON.stringify(payload),
+    });
+};
+
+const createData = function (data, proxyAddress = null) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "create_data", {
+      data: JSON.stringify(data),
+      proxy_address: proxyAddress,
     });
 };
 
@@ -79,6 +221,258 @@ const createCommit = function (
     );
 };
 
+const draftCommit = function (
+  dataAddress,
+  creatorsIds,
+  parentCommitAddresses = [],
+  message = "",
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "draft_commit", {
+        dataId: dataAddress,
+        parentsIds: parentCommitAddresses,
+        message,
+        timestamp,
+        creatorsIds,
+      })
+    );
+};
+
+const signCommit = function (draftAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "sign_commit", {
+        draft_address: draftAddress,
+      })
+    );
+};
+
+const getLatestDraft = function (draftAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_latest_draft", {
+        draft_address: draftAddress,
+      })
+    );
+};
+
+const getMyDrafts = function () {
+  return async (caller) =>
+    parseResponse(await caller.call("uprtcl", "uprtcl", "get_my_drafts", {}));
+};
+
+const cloneCommit = function (commit, previousAddress = null) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "clone_commit", {
+      previous_address: previousAddress,
+      commit,
+    });
+};
+
+/** History */
+
+const getCommitHistory = function (commitAddress, limit, cursor = null) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_commit_history", {
+        commit_id: commitAddress,
+        limit,
+        cursor,
+      })
+    );
+};
+
+const findMergeBase = function (commitA, commitB) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "find_merge_base", {
+        commit_a: commitA,
+        commit_b: commitB,
+      })
+    );
+};
+
+/** Merge */
+
+const commitData = function (data, parents, message) {
+  return async (caller) => {
+    const { Ok: dataId } = await createData(data)(caller);
+    return createCommit(dataId, parents, message)(caller);
+  };
+};
+
+const diffCommits = function (from, to) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "diff_commits", { from, to })
+    );
+};
+
+const mergeCommits = function (ours, theirs, message, timestamp = Date.now()) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "merge_commits", {
+        ours,
+        theirs,
+        message,
+        timestamp,
+      })
+    );
+};
+
+const commitPatch = function (
+  perspectiveAddress,
+  baseCommit,
+  patch,
+  message = "",
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "commit_patch", {
+      perspective_address: perspectiveAddress,
+      base_commit: baseCommit,
+      patch,
+      message,
+      timestamp,
+    });
+};
+
+const cherryPick = function (
+  perspectiveAddress,
+  commitAddress,
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "cherry_pick", {
+        perspective_address: perspectiveAddress,
+        commit_address: commitAddress,
+        timestamp,
+      })
+    );
+};
+
+const revertCommit = function (
+  perspectiveAddress,
+  commitAddress,
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "revert_commit", {
+        perspective_address: perspectiveAddress,
+        commit_address: commitAddress,
+        timestamp,
+      })
+    );
+};
+
+/** Permissions */
+
+const inviteCollaborator = function (
+  perspectiveAddress,
+  agentAddress,
+  role,
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "invite_collaborator", {
+        perspective_address: perspectiveAddress,
+        agent_address: agentAddress,
+        role,
+        timestamp,
+      })
+    );
+};
+
+const getMyInvitations = function () {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_my_invitations", {})
+    );
+};
+
+const acceptInvite = function (invitationAddress) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "accept_invite", {
+      invitation_address: invitationAddress,
+    });
+};
+
+const revokeCollaborator = function (perspectiveAddress, agentAddress) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "revoke_collaborator", {
+      perspective_address: perspectiveAddress,
+      agent_address: agentAddress,
+    });
+};
+
+const getPermissions = function (perspectiveAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_permissions", {
+        perspective_address: perspectiveAddress,
+      })
+    );
+};
+
+/** Proposals */
+
+const createProposal = function (
+  toPerspectiveAddress,
+  updates,
+  description = null,
+  fromPerspectiveAddress = null,
+  timestamp = Date.now()
+) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "create_proposal", {
+        to_perspective_address: toPerspectiveAddress,
+        from_perspective_address: fromPerspectiveAddress,
+        updates,
+        description,
+        timestamp,
+      })
+    );
+};
+
+const getPerspectiveProposals = function (perspectiveAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_perspective_proposals", {
+        perspective_address: perspectiveAddress,
+      })
+    );
+};
+
+const getProposalStatus = function (proposalAddress) {
+  return async (caller) =>
+    parseResponse(
+      await caller.call("uprtcl", "uprtcl", "get_proposal_status", {
+        proposal_address: proposalAddress,
+      })
+    );
+};
+
+const updateProposalStatus = function (proposalAddress, status) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "update_proposal_status", {
+      proposal_address: proposalAddress,
+      status,
+    });
+};
+
+const executeProposal = function (proposalAddress) {
+  return async (caller) =>
+    await caller.call("uprtcl", "uprtcl", "execute_proposal", {
+      proposal_address: proposalAddress,
+    });
+};
+
 /** Helper functions */
 
 const createCommitInPerspective = function (
@@ -192,6 +586,29 @@ const parseEntryResult = function (entry) {
   };
 };
 
+/**
+ * Compact JSON with the keys of every object sorted by their UTF-8 bytes, as signed by the zome
+ *
+ * Only integers between -(2^53 - 1) and 2^53 - 1 are allowed as numbers
+ */
+const compareUtf8 = (k1, k2) => Buffer.compare(Buffer.from(k1), Buffer.from(k2));
+
+const canonicalJson = function (value) {
+  if (Array.isArray(value)) {
+    return `[${value.map(canonicalJson).join(",")}]`;
+  }
+  if (value !== null && typeof value === "object") {
+    const keys = Object.keys(value).sort(compareUtf8);
+    return `{${keys
+      .map((key) => `${JSON.stringify(key)}:${canonicalJson(value[key])}`)
+      .join(",")}}`;
+  }
+  if (typeof value === "number" && !Number.isSafeInteger(value)) {
+    throw new Error(`Canonical json only supports safe integers, found ${value}`);
+  }
+  return JSON.stringify(value);
+};
+
 const parseEntry = function (entry) {
   const parseable = parseResponse(entry);
   return JSON.parse(parseable.App[1]);
@@ -200,16 +617,55 @@ const parseEntry = function (entry) {
 module.exports = {
   getEntry,
   createPerspective,
+  clonePerspective,
+  forkPerspective,
+  getUpstreamStatus,
+  pullPerspective,
   getContextPerspectives,
+  getMyPerspectives,
+  getAgentPerspectives,
+  getPerspectivesByName,
   getPerspectiveDetails,
   updatePerspectiveDetails,
+  commitToPerspective,
+  getPerspectiveHeads,
+  getPerspectiveHeadHistory,
+  getPerspectiveNameHistory,
+  getPerspectiveContextHistory,
+  getCanonicalJson,
+  createData,
   createCommit,
+  cloneCommit,
+  draftCommit,
+  signCommit,
+  getLatestDraft,
+  getMyDrafts,
+  getCommitHistory,
+  findMergeBase,
+  commitData,
+  diffCommits,
+  mergeCommits,
+  commitPatch,
+  cherryPick,
+  revertCommit,
+  inviteCollaborator,
+  getMyInvitations,
+  acceptInvite,
+  revokeCollaborator,
+  getPermissions,
+  createProposal,
+  getPerspectiveProposals,
+  getProposalStatus,
+  updateProposalStatus,
+  executeProposal,
   createCommitInPerspective,
   createNewPerspectiveAndCommit,
   buildPerspective,
   buildCommit,
   buildProvenance,
   chain,
+  canonicalJson,
   parseEntryResult,
   parseEntry,
+  parseResponse,
 };
@@ -0,0 +1,72 @@
+const {
+  createPerspective,
+  getPerspectiveDetails,
+  updatePerspectiveDetails,
+} = require("./utils");
+
+// Contents that broke the legacy "content:{},count:{}" encoding
+const TRICKY_CONTENTS = [
+  "Notes: draft, v2",
+  "content:evil,count:99",
+  '{"content":"nested","count":3}',
+  'quotes " and \\ backslashes',
+  "línea\nnueva\ty tabulador",
+  "日本語のノート",
+  "emoji 🌱🍄, with: separators",
+  ",",
+  ":",
+];
+
+// Seeded so that failures can be reproduced with VERSIONED_TAGS_SEED=<seed>
+const SEED = parseInt(process.env.VERSIONED_TAGS_SEED || Date.now() % 2 ** 32);
+
+// mulberry32, returning floats in [0, 1) like Math.random
+const seededRandom = function (seed) {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+};
+
+const randomUnicodeString = function (random, length) {
+  let result = "";
+  for (let i = 0; i < length; i++) {
+    // Any code point outside the surrogate range
+    let codePoint = Math.floor(random() * 0x10ffff);
+    if (codePoint >= 0xd800 && codePoint <= 0xdfff) codePoint -= 0x800;
+    result += String.fromCodePoint(codePoint);
+  }
+  return result;
+};
+
+module.exports = (orchestrator, config) => {
+  orchestrator.registerScenario(
+    "perspective names and contexts survive any unicode content",
+    async (s, t) => {
+      const { alice } = await s.players({ alice: config }, true);
+
+      const random = seededRandom(SEED);
+      const randomContents = [...Array(10).keys()].map(() =>
+        randomUnicodeString(random, 1 + Math.floor(random() * 40))
+      );
+
+      for (const content of [...TRICKY_CONTENTS, ...randomContents]) {
+        const perspectiveAddress = await createPerspective()(alice);
+        const result = await updatePerspectiveDetails(perspectiveAddress, {
+          name: content,
+          context: content,
+        })(alice);
+        t.ok(result.Ok === null, `${JSON.stringify(content)} (seed ${SEED})`);
+        await s.consistency();
+
+        const details = await getPerspectiveDetails(perspectiveAddress)(alice);
+        t.equal(details.name, content);
+        t.equal(details.context, content);
+      }
+    }
+  );
+};